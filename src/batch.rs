@@ -0,0 +1,188 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use macroquad::prelude::*;
+use macroquad::miniquad::{
+    Bindings, BlendFactor, BlendState, BlendValue, Buffer, BufferLayout, BufferType, Equation,
+    PassAction, Pipeline, PipelineParams, Shader, ShaderMeta, UniformBlockLayout, UniformDesc,
+    UniformType, VertexAttribute, VertexFormat, VertexStep,
+};
+use crate::verlet::VerletObject;
+
+// same tesselation as macroquad's draw_circle
+const CIRCLE_SIDES: usize = 20;
+const INITIAL_INSTANCE_CAPACITY: usize = 1024;
+
+// per-instance data uploaded once per frame, one entry per rendered object
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CircleInstance {
+    pub pos_curr: [f32; 2],
+    pub pos_old: [f32; 2],
+    pub radius: f32,
+    pub temperature: f32,
+    pub color: [f32; 4],
+}
+
+impl CircleInstance {
+    pub fn new(obj: &VerletObject, color: Color) -> Self {
+        Self {
+            pos_curr: [obj.position_current.x, obj.position_current.y],
+            pos_old: [obj.position_old.x, obj.position_old.y],
+            radius: obj.radius,
+            temperature: obj.temperature,
+            color: color.into(),
+        }
+    }
+}
+
+#[repr(C)]
+struct Uniforms {
+    projection: Mat4,
+}
+
+thread_local! {
+    // miniquad 0.3 can't delete pipelines, so every pair of shader sources is only compiled once,
+    // reloading or resetting to sources seen before reuses their pipeline instead of leaking a new one
+    static PIPELINES: RefCell<HashMap<(String, String), Pipeline>> = RefCell::new(HashMap::new());
+}
+
+pub fn create_pipeline(vertex_shader: &str, fragment_shader: &str) -> Result<Pipeline, String> {
+    let key: (String, String) = (vertex_shader.to_string(), fragment_shader.to_string());
+    if let Some(pipeline) = PIPELINES.with(|pipelines| pipelines.borrow().get(&key).copied()) {
+        return Ok(pipeline);
+    }
+    let ctx = unsafe { get_internal_gl() }.quad_context;
+    let shader: Shader = Shader::new(
+        ctx,
+        vertex_shader,
+        fragment_shader,
+        ShaderMeta {
+            images: vec![],
+            uniforms: UniformBlockLayout {
+                uniforms: vec![UniformDesc::new("Projection", UniformType::Mat4)],
+            },
+        },
+    ).map_err(|error| error.to_string())?;
+
+    let pipeline: Pipeline = Pipeline::with_params(
+        ctx,
+        &[
+            BufferLayout::default(),
+            BufferLayout {
+                step_func: VertexStep::PerInstance,
+                ..Default::default()
+            },
+        ],
+        &[
+            VertexAttribute::with_buffer("position", VertexFormat::Float2, 0),
+            VertexAttribute::with_buffer("inst_pos_curr", VertexFormat::Float2, 1),
+            VertexAttribute::with_buffer("inst_pos_old", VertexFormat::Float2, 1),
+            VertexAttribute::with_buffer("inst_radius", VertexFormat::Float1, 1),
+            VertexAttribute::with_buffer("inst_temperature", VertexFormat::Float1, 1),
+            VertexAttribute::with_buffer("inst_color", VertexFormat::Float4, 1),
+        ],
+        shader,
+        PipelineParams {
+            color_blend: Some(BlendState::new(
+                Equation::Add,
+                BlendFactor::Value(BlendValue::SourceAlpha),
+                BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+            )),
+            ..Default::default()
+        },
+    );
+    PIPELINES.with(|pipelines| pipelines.borrow_mut().insert(key, pipeline));
+    Ok(pipeline)
+}
+
+pub struct CircleBatch {
+    // false on GLES2 / WebGL1 without instanced arrays, objects are then drawn one by one
+    pub instancing: bool,
+    pub instances: Vec<CircleInstance>,
+    pub default_pipeline: Pipeline,
+    bindings: Bindings,
+    index_count: i32,
+    capacity: usize,
+}
+
+impl CircleBatch {
+    pub fn new() -> Self {
+        let ctx = unsafe { get_internal_gl() }.quad_context;
+
+        // unit circle as a triangle fan, uv matches the one of draw_circle
+        let mut vertices: Vec<[f32; 2]> = Vec::with_capacity(CIRCLE_SIDES + 2);
+        let mut indices: Vec<u16> = Vec::with_capacity(CIRCLE_SIDES * 3);
+        vertices.push([0.0, 0.0]);
+        for i in 0..=CIRCLE_SIDES {
+            let angle: f32 = i as f32 / CIRCLE_SIDES as f32 * std::f32::consts::PI * 2.0;
+            vertices.push([angle.cos(), angle.sin()]);
+            if i != CIRCLE_SIDES {
+                indices.extend_from_slice(&[0, i as u16 + 1, i as u16 + 2]);
+            }
+        }
+
+        let vertex_buffer: Buffer = Buffer::immutable(ctx, BufferType::VertexBuffer, &vertices);
+        let index_buffer: Buffer = Buffer::immutable(ctx, BufferType::IndexBuffer, &indices);
+        let instance_buffer: Buffer = Buffer::stream(
+            ctx,
+            BufferType::VertexBuffer,
+            INITIAL_INSTANCE_CAPACITY * std::mem::size_of::<CircleInstance>(),
+        );
+
+        Self {
+            instancing: ctx.features().instancing,
+            instances: Vec::with_capacity(INITIAL_INSTANCE_CAPACITY),
+            default_pipeline: create_pipeline(
+                crate::shaders::DEFAULT_VERTEX_SHADER,
+                crate::shaders::OBJECT_COLOR_FRAGMENT_SHADER,
+            ).unwrap(),
+            bindings: Bindings {
+                vertex_buffers: vec![vertex_buffer, instance_buffer],
+                index_buffer,
+                images: vec![],
+            },
+            index_count: indices.len() as i32,
+            capacity: INITIAL_INSTANCE_CAPACITY,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    pub fn push(&mut self, instance: CircleInstance) {
+        self.instances.push(instance);
+    }
+
+    // draws every pushed instance in a single instanced draw call
+    pub fn draw(&mut self, pipeline: Pipeline) {
+        if self.instances.is_empty() {
+            return;
+        }
+        let mut gl = unsafe { get_internal_gl() };
+        // everything macroquad batched so far has to end up below the objects
+        gl.flush();
+        let projection: Mat4 = gl.quad_gl.get_projection_matrix();
+        let ctx = gl.quad_context;
+
+        if self.instances.len() > self.capacity {
+            while self.capacity < self.instances.len() {
+                self.capacity *= 2;
+            }
+            self.bindings.vertex_buffers[1].delete();
+            self.bindings.vertex_buffers[1] = Buffer::stream(
+                ctx,
+                BufferType::VertexBuffer,
+                self.capacity * std::mem::size_of::<CircleInstance>(),
+            );
+        }
+        self.bindings.vertex_buffers[1].update(ctx, &self.instances);
+
+        ctx.begin_default_pass(PassAction::Nothing);
+        ctx.apply_pipeline(&pipeline);
+        ctx.apply_bindings(&self.bindings);
+        ctx.apply_uniforms(&Uniforms { projection });
+        ctx.draw(0, self.index_count, self.instances.len() as i32);
+        ctx.end_render_pass();
+    }
+}
@@ -0,0 +1,287 @@
+use macroquad::prelude::get_time;
+use crate::{vector::Vec2, verlet::{VerletObject, VerletObjects}, grid::CellGrid, nbody::{NBody, NBodyMethod}, solver::{Solver, Broadphase, Contact, ContactBody}, context::{self, DENSITY_PRESET_SPAWNS}};
+
+pub const GRID_BENCHMARK_OBJECT_COUNTS: [usize; 3] = [1_000, 5_000, 20_000];
+pub const STORAGE_BENCHMARK_OBJECT_COUNT: usize = 10_000;
+// the direct reference is quadratic, so the counts stay lower than for the grids
+pub const N_BODY_BENCHMARK_OBJECT_COUNTS: [usize; 3] = [1_000, 2_000, 5_000];
+
+// small enough for 20k objects to fit onto a FHD screen
+const BENCHMARK_RADIUS: f32 = 3.0;
+const BENCHMARK_SETTLE_FRAMES: usize = 30;
+const BENCHMARK_FRAMES: usize = 30;
+const BENCHMARK_FRAME_TIME: f32 = 1.0 / 60.0;
+const BENCHMARK_SUBSTEPS: usize = 8;
+const N_BODY_BENCHMARK_REPEATS: usize = 3;
+const FLUID_CHECK_SECONDS: usize = 15;
+
+// name and a function setting the solver up
+pub type Configuration = (&'static str, fn(&mut Solver));
+
+pub struct FluidCheckResult {
+    pub seconds: usize,
+    pub flatness: f32,
+    pub tolerance: f32,
+}
+
+impl FluidCheckResult {
+    pub fn passed(&self) -> bool {
+        self.flatness <= self.tolerance
+    }
+}
+
+pub struct BenchmarkResult {
+    pub name: String,
+    pub object_count: usize,
+    pub ms_per_frame: f64,
+    pub pair_checks: usize,
+}
+
+// spawns a scene and lets it settle so that every benchmarked configuration starts from
+// the same, already resolved object positions
+pub fn settled_scene(object_count: usize) -> Solver {
+    let mut solver: Solver = Solver::new(context::screen_size());
+    solver.spawn_radius = BENCHMARK_RADIUS;
+    solver.spawn_count(object_count);
+    settle(&mut solver);
+    solver
+}
+
+fn settle(solver: &mut Solver) {
+    for _ in 0..BENCHMARK_SETTLE_FRAMES {
+        solver.update_with_substep(BENCHMARK_FRAME_TIME, BENCHMARK_SUBSTEPS);
+    }
+}
+
+// runs the simulation headless (without rendering) and returns the average frame time
+pub fn time_frames(solver: &mut Solver) -> f64 {
+    let start: f64 = get_time();
+    for _ in 0..BENCHMARK_FRAMES {
+        solver.update_with_substep(BENCHMARK_FRAME_TIME, BENCHMARK_SUBSTEPS);
+    }
+    (get_time() - start) * 1000.0 / BENCHMARK_FRAMES as f64
+}
+
+// same as time_frames but for a single step of the simulation, called once per substep
+pub fn time_substeps(mut substep: impl FnMut()) -> f64 {
+    let start: f64 = get_time();
+    for _ in 0..BENCHMARK_FRAMES * BENCHMARK_SUBSTEPS {
+        substep();
+    }
+    (get_time() - start) * 1000.0 / BENCHMARK_FRAMES as f64
+}
+
+pub fn compare(object_counts: &[usize], configurations: &[Configuration]) -> Vec<BenchmarkResult> {
+    let mut results: Vec<BenchmarkResult> = vec![];
+    for &object_count in object_counts {
+        results.extend(compare_scene(&settled_scene(object_count), configurations));
+    }
+    results
+}
+
+// runs every configuration on a copy of the same settled scene
+pub fn compare_scene(scene: &Solver, configurations: &[Configuration]) -> Vec<BenchmarkResult> {
+    let mut results: Vec<BenchmarkResult> = vec![];
+    for (name, configure) in configurations {
+        let mut solver: Solver = Solver::new(scene.world_size);
+        solver.spawn_radius = scene.spawn_radius;
+        configure(&mut solver);
+        solver.verlet_objects = scene.verlet_objects.clone();
+        let ms_per_frame: f64 = time_frames(&mut solver);
+        results.push(BenchmarkResult {
+            name: name.to_string(),
+            object_count: scene.verlet_objects.len(),
+            ms_per_frame,
+            pair_checks: solver.pair_checks,
+        });
+    }
+    results
+}
+
+pub fn grid_benchmark() -> Vec<BenchmarkResult> {
+    compare(&GRID_BENCHMARK_OBJECT_COUNTS, &[
+        ("Flat grid, pairs once", |solver| {
+            solver.broadphase = Broadphase::Flat;
+        }),
+        ("Flat grid, pairs twice", |solver| {
+            solver.broadphase = Broadphase::Flat;
+            solver.solve_pairs_once = false;
+        }),
+        ("Nested grid (legacy)", |solver| {
+            solver.broadphase = Broadphase::Nested;
+        }),
+    ])
+}
+
+// the mixed radii of the density preset make the uniform grid cells fit the largest objects
+pub fn density_benchmark() -> Vec<BenchmarkResult> {
+    let mut scene: Solver = Solver::new(context::screen_size());
+    for (radius, count) in DENSITY_PRESET_SPAWNS {
+        scene.spawn_radius = radius;
+        scene.spawn_count(count);
+    }
+    settle(&mut scene);
+    compare_scene(&scene, &[
+        ("Flat grid", |solver| {
+            solver.broadphase = Broadphase::Flat;
+        }),
+        ("Multi-level grid", |solver| {
+            solver.broadphase = Broadphase::MultiLevel;
+        }),
+    ])
+}
+
+// array-of-structs versions of the hot loops, the way objects were stored before
+// the structure-of-arrays storage, kept as a baseline for the storage benchmark
+fn aos_update(objects: &mut [VerletObject], gravity: Vec2, dt: f32) {
+    for obj in objects.iter_mut() {
+        obj.accelerate(gravity);
+        obj.update_position(dt);
+    }
+}
+
+fn aos_collide(solver: &Solver, objects: &mut [VerletObject], i: usize, j: usize) {
+    let (obj_1, obj_2) = if i < j {
+        let (left, right) = objects.split_at_mut(j);
+        (&mut left[i], &mut right[0])
+    } else {
+        let (left, right) = objects.split_at_mut(i);
+        (&mut right[0], &mut left[j])
+    };
+    let contact: Option<Contact> = solver.body_contact(
+        &ContactBody::from_object(obj_1),
+        &ContactBody::from_object(obj_2),
+    );
+    if let Some(contact) = contact {
+        obj_1.position_current += contact.shift_1;
+        obj_2.position_current += contact.shift_2;
+        obj_1.pressure += contact.overlap;
+        obj_2.pressure += contact.overlap;
+        obj_1.contact_count += 1;
+        obj_2.contact_count += 1;
+        obj_1.temperature -= contact.heat / obj_1.heat_capacity;
+        obj_2.temperature += contact.heat / obj_2.heat_capacity;
+    }
+}
+
+// compares the array-of-structs and structure-of-arrays layouts on the loops that
+// touch every object, single threaded so only the memory layout differs
+pub fn storage_benchmark() -> Vec<BenchmarkResult> {
+    let mut scene: Solver = settled_scene(STORAGE_BENCHMARK_OBJECT_COUNT);
+    scene.parallel = false;
+    let dt: f32 = BENCHMARK_FRAME_TIME / BENCHMARK_SUBSTEPS as f32;
+    let gravity: Vec2 = scene.gravity;
+    let settled: VerletObjects = scene.verlet_objects.clone();
+    let mut results: Vec<BenchmarkResult> = vec![];
+    let mut push = |name: &str, ms_per_frame: f64, pair_checks: usize| {
+        results.push(BenchmarkResult {
+            name: name.to_string(),
+            object_count: STORAGE_BENCHMARK_OBJECT_COUNT,
+            ms_per_frame,
+            pair_checks,
+        });
+    };
+
+    // integration and gravity, objects are left falling freely
+    let mut aos_objects: Vec<VerletObject> = settled.iter().collect();
+    push("AoS integrate + gravity", time_substeps(|| aos_update(&mut aos_objects, gravity, dt)), 0);
+    push("SoA integrate + gravity", time_substeps(|| {
+        scene.apply_gravity();
+        scene.update_positions(dt);
+    }), 0);
+
+    // narrow phase over the same pairs of the settled scene
+    let mut grid: CellGrid = CellGrid::new();
+    grid.build(&settled.position_current, scene.cell_size, scene.world_size.x, scene.world_size.y);
+    let mut aos_objects: Vec<VerletObject> = settled.iter().collect();
+    let mut pair_checks: usize = 0;
+    let ms_per_frame: f64 = time_substeps(|| grid.for_each_pair(|i, j| {
+        pair_checks += 1;
+        aos_collide(&scene, &mut aos_objects, i, j);
+    }));
+    push("AoS narrow phase", ms_per_frame, pair_checks / (BENCHMARK_FRAMES * BENCHMARK_SUBSTEPS));
+    let mut soa_objects: VerletObjects = settled.clone();
+    let mut pair_checks: usize = 0;
+    let ms_per_frame: f64 = time_substeps(|| grid.for_each_pair(|i, j| {
+        pair_checks += 1;
+        scene.collide(&mut soa_objects, i, j);
+    }));
+    push("SoA narrow phase", ms_per_frame, pair_checks / (BENCHMARK_FRAMES * BENCHMARK_SUBSTEPS));
+
+    results
+}
+
+// compares the barnes-hut approximation with the direct sum over every pair on settled scenes,
+// the frame time is the time of one evaluation per substep and the error is relative to the direct sum
+pub fn n_body_benchmark() -> Vec<BenchmarkResult> {
+    let mut results: Vec<BenchmarkResult> = vec![];
+    for object_count in N_BODY_BENCHMARK_OBJECT_COUNTS {
+        let scene: Solver = settled_scene(object_count);
+        let objects: &VerletObjects = &scene.verlet_objects;
+        let mut n_body: NBody = NBody::default();
+        let time = |n_body: &mut NBody| {
+            let start: f64 = get_time();
+            let mut accelerations: Vec<Vec2> = vec![];
+            for _ in 0..N_BODY_BENCHMARK_REPEATS {
+                accelerations = n_body.accelerations(&objects.position_current, &objects.radius);
+            }
+            let ms_per_frame: f64 = (get_time() - start) * 1000.0 * BENCHMARK_SUBSTEPS as f64 / N_BODY_BENCHMARK_REPEATS as f64;
+            (accelerations, ms_per_frame)
+        };
+
+        n_body.method = NBodyMethod::Direct;
+        let (reference, ms_per_frame) = time(&mut n_body);
+        results.push(BenchmarkResult {
+            name: NBodyMethod::Direct.name().to_string(),
+            object_count,
+            ms_per_frame,
+            pair_checks: object_count * object_count,
+        });
+
+        n_body.method = NBodyMethod::BarnesHut;
+        let (approximation, ms_per_frame) = time(&mut n_body);
+        // summed over all objects so the few objects with a near zero net pull don't dominate it
+        let mut error: f32 = 0.0;
+        let mut magnitude: f32 = 0.0;
+        for (&exact, &approximate) in reference.iter().zip(approximation.iter()) {
+            error += (approximate - exact).len();
+            magnitude += exact.len();
+        }
+        results.push(BenchmarkResult {
+            name: format!(
+                "{} (theta {}, {} nodes, error {:.2}%)",
+                NBodyMethod::BarnesHut.name(), n_body.theta, n_body.quad_tree.node_count(), error / magnitude.max(f32::EPSILON) * 100.0,
+            ),
+            object_count,
+            ms_per_frame,
+            pair_checks: 0,
+        });
+    }
+    results
+}
+
+// headless dam break, the liquid has to settle to a surface flat within the diameter of its objects
+pub fn fluid_check(world_size: Vec2) -> FluidCheckResult {
+    let mut solver: Solver = Solver::new(world_size);
+    context::dam_break_scene(&mut solver);
+    for _ in 0..FLUID_CHECK_SECONDS * 60 {
+        solver.update_with_substep(BENCHMARK_FRAME_TIME, BENCHMARK_SUBSTEPS);
+    }
+    FluidCheckResult {
+        seconds: FLUID_CHECK_SECONDS,
+        flatness: solver.surface_flatness(),
+        tolerance: solver.spawn_radius * 2.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a world smaller than the usual window keeps the test quick
+    #[test]
+    fn dam_break_settles_flat() {
+        let result: FluidCheckResult = fluid_check(Vec2 {x: 360.0, y: 180.0});
+        assert!(result.passed(), "surface spread {} exceeds {}", result.flatness, result.tolerance);
+    }
+}
@@ -0,0 +1,208 @@
+use macroquad::prelude::*;
+use crate::verlet::{VerletObject, VerletObjects};
+
+// sampled from matplotlib's colormaps at 9 evenly spaced stops
+const VIRIDIS: [(f32, f32, f32); 9] = [
+    (0.267, 0.005, 0.329),
+    (0.279, 0.175, 0.483),
+    (0.230, 0.322, 0.546),
+    (0.173, 0.449, 0.558),
+    (0.128, 0.567, 0.551),
+    (0.158, 0.684, 0.502),
+    (0.369, 0.789, 0.383),
+    (0.678, 0.864, 0.190),
+    (0.993, 0.906, 0.144),
+];
+
+const INFERNO: [(f32, f32, f32); 9] = [
+    (0.001, 0.000, 0.014),
+    (0.087, 0.045, 0.225),
+    (0.258, 0.039, 0.406),
+    (0.416, 0.090, 0.433),
+    (0.578, 0.148, 0.404),
+    (0.736, 0.216, 0.330),
+    (0.865, 0.317, 0.226),
+    (0.955, 0.469, 0.100),
+    (0.988, 0.998, 0.645),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorMapSource {
+    ObjectColor,
+    Speed,
+    Temperature,
+    Charge,
+    Radius,
+    Pressure,
+    Neighbors,
+}
+
+impl ColorMapSource {
+    pub const ALL: [Self; 7] = [
+        Self::ObjectColor,
+        Self::Speed,
+        Self::Temperature,
+        Self::Charge,
+        Self::Radius,
+        Self::Pressure,
+        Self::Neighbors,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ObjectColor => "Object color",
+            Self::Speed => "Speed",
+            Self::Temperature => "Temperature",
+            Self::Charge => "Charge",
+            Self::Radius => "Radius",
+            Self::Pressure => "Pressure (overlap)",
+            Self::Neighbors => "Neighbor count",
+        }
+    }
+
+    // dt is needed to turn the per-substep verlet displacement into a speed
+    pub fn value(&self, obj: &VerletObject, dt: f32) -> f32 {
+        match self {
+            Self::ObjectColor => 0.0,
+            Self::Speed => (obj.position_current - obj.position_old).len() / dt.max(f32::EPSILON),
+            Self::Temperature => obj.temperature,
+            Self::Charge => obj.charge,
+            Self::Radius => obj.radius,
+            Self::Pressure => obj.pressure,
+            Self::Neighbors => obj.contact_count as f32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorMapPalette {
+    Viridis,
+    Inferno,
+    Gradient,
+}
+
+impl ColorMapPalette {
+    pub const ALL: [Self; 3] = [
+        Self::Viridis,
+        Self::Inferno,
+        Self::Gradient,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Viridis => "Viridis",
+            Self::Inferno => "Inferno",
+            Self::Gradient => "Custom gradient",
+        }
+    }
+}
+
+pub struct ColorMapContext {
+    pub source: ColorMapSource,
+    pub palette: ColorMapPalette,
+    pub gradient_start: Color,
+    pub gradient_end: Color,
+
+    // value range mapped onto the palette, recalculated every frame when auto_range is set
+    pub auto_range: bool,
+    pub range_min: f32,
+    pub range_max: f32,
+
+    pub show_legend: bool,
+}
+
+impl ColorMapContext {
+    pub fn default() -> Self {
+        Self {
+            source: ColorMapSource::ObjectColor,
+            palette: ColorMapPalette::Viridis,
+            gradient_start: BLUE,
+            gradient_end: RED,
+
+            auto_range: true,
+            range_min: 0.0,
+            range_max: 1.0,
+
+            show_legend: true,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.source != ColorMapSource::ObjectColor
+    }
+
+    pub fn update_range(&mut self, objects: &VerletObjects, dt: f32) {
+        if !self.auto_range || objects.is_empty() {
+            return;
+        }
+        let mut min: f32 = f32::INFINITY;
+        let mut max: f32 = f32::NEG_INFINITY;
+        for obj in objects.iter() {
+            let value: f32 = self.source.value(&obj, dt);
+            min = min.min(value);
+            max = max.max(value);
+        }
+        self.range_min = min;
+        self.range_max = max;
+    }
+
+    pub fn sample(&self, t: f32) -> Color {
+        let t: f32 = t.clamp(0.0, 1.0);
+        let stops: &[(f32, f32, f32)] = match self.palette {
+            ColorMapPalette::Viridis => &VIRIDIS,
+            ColorMapPalette::Inferno => &INFERNO,
+            ColorMapPalette::Gradient => {
+                let (a, b) = (self.gradient_start, self.gradient_end);
+                return Color::new(
+                    a.r + (b.r - a.r) * t,
+                    a.g + (b.g - a.g) * t,
+                    a.b + (b.b - a.b) * t,
+                    a.a + (b.a - a.a) * t,
+                );
+            },
+        };
+        let scaled: f32 = t * (stops.len() - 1) as f32;
+        let i: usize = (scaled.floor() as usize).min(stops.len() - 2);
+        let f: f32 = scaled - i as f32;
+        let (r1, g1, b1) = stops[i];
+        let (r2, g2, b2) = stops[i + 1];
+        Color::new(r1 + (r2 - r1) * f, g1 + (g2 - g1) * f, b1 + (b2 - b1) * f, 1.0)
+    }
+
+    pub fn color(&self, obj: &VerletObject, dt: f32) -> Color {
+        let span: f32 = self.range_max - self.range_min;
+        let t: f32 = if span > f32::EPSILON {
+            (self.source.value(obj, dt) - self.range_min) / span
+        } else {
+            0.0
+        };
+        let mut color: Color = self.sample(t);
+        // keep the transparency the object was spawned with
+        color.a *= obj.color.a;
+        color
+    }
+
+    pub fn draw_legend(&self) {
+        let width: f32 = 200.0;
+        let height: f32 = 12.0;
+        let x: f32 = screen_width() - width - 20.0;
+        let y: f32 = screen_height() - height - 20.0;
+        let slices: usize = 50;
+        let slice_width: f32 = width / slices as f32;
+
+        draw_rectangle(x - 6.0, y - 26.0, width + 12.0, height + 50.0, Color::new(0.0, 0.0, 0.0, 0.6));
+        draw_text(self.source.name(), x, y - 8.0, 18.0, WHITE);
+        for i in 0..slices {
+            let mut color: Color = self.sample(i as f32 / (slices - 1) as f32);
+            color.a = 1.0;
+            draw_rectangle(x + i as f32 * slice_width, y, slice_width + 0.5, height, color);
+        }
+        draw_rectangle_lines(x, y, width, height, 1.0, WHITE);
+
+        let min_text: String = format!("{:.2}", self.range_min);
+        let max_text: String = format!("{:.2}", self.range_max);
+        let max_dimensions: TextDimensions = measure_text(&max_text, None, 16, 1.0);
+        draw_text(&min_text, x, y + height + 14.0, 16.0, WHITE);
+        draw_text(&max_text, x + width - max_dimensions.width, y + height + 14.0, 16.0, WHITE);
+    }
+}
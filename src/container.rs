@@ -0,0 +1,477 @@
+use std::f32::consts::{PI, TAU};
+use macroquad::prelude::{draw_line, GRAY};
+use crate::{vector::Vec2, thermal::WallHeat};
+
+const OUTLINE_SEGMENTS: usize = 128;
+const OUTLINE_TRACE_STEPS: usize = 64;
+
+// signed distances are taken relative to the window center and scaled by the container
+// scale (half of the smaller window dimension), the distance is negative inside the container
+// example custom containers, see SdfPart::parse_all for the syntax
+pub const CUSTOM_SDFS: [(&str, &str); 3] = [
+    ("Hourglass", "circle 0 -0.45 0.45; circle 0 0.45 0.45; box 0 0 0.08 0.2"),
+    ("Cross", "box 0 0 0.9 0.3 0.1; box 0 0 0.3 0.9 0.1"),
+    ("Funnel", "box 0 -0.3 0.9 0.5 0.1; capsule 0 0.1 0 0.8 0.08; cut circle -0.75 0.3 0.6; cut circle 0.75 0.3 0.6"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SdfPrimitive {
+    Circle {center: Vec2, radius: f32},
+    Box {center: Vec2, half_width: f32, half_height: f32, corner_radius: f32},
+    Capsule {start: Vec2, end: Vec2, radius: f32},
+}
+
+// one primitive of a custom container, sizes are relative to the container scale like those of
+// the other shapes, parts are applied in order, each adding its inside or cutting it away
+#[derive(Clone, Copy, PartialEq)]
+pub struct SdfPart {
+    pub primitive: SdfPrimitive,
+    pub cut: bool,
+}
+
+impl SdfPart {
+    // parses descriptions like "circle 0 0 0.9; cut box 0 0 0.2 0.2" with one part per line or
+    // per semicolon: circle x y radius, box x y half_width half_height [corner_radius],
+    // capsule x1 y1 x2 y2 radius, any of them prefixed with cut to remove it instead
+    pub fn parse_all(source: &str) -> Result<Vec<Self>, String> {
+        let parts: Vec<Self> = source
+            .split(['\n', ';'])
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(Self::parse)
+            .collect::<Result<_, _>>()?;
+        if parts.iter().all(|part| part.cut) {
+            return Err("Nothing to cut from, add a circle, box or capsule without cut".to_string());
+        }
+        Ok(parts)
+    }
+
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut tokens: Vec<&str> = line.split_whitespace().collect();
+        let cut: bool = tokens[0].eq_ignore_ascii_case("cut");
+        if cut {
+            tokens.remove(0);
+        }
+        let name: String = tokens.first().map_or(String::new(), |name| name.to_lowercase());
+        let values: Vec<f32> = tokens.iter().skip(1)
+            .map(|token| token.parse::<f32>().map_err(|_| format!("\"{}\" in \"{}\" isn't a number", token, line)))
+            .collect::<Result<_, _>>()?;
+        let point = |i: usize| Vec2 {x: values[i], y: values[i + 1]};
+        let primitive: SdfPrimitive = match (name.as_str(), values.len()) {
+            ("circle", 3) => SdfPrimitive::Circle {center: point(0), radius: values[2]},
+            ("box", 4 | 5) => SdfPrimitive::Box {
+                center: point(0),
+                half_width: values[2],
+                half_height: values[3],
+                corner_radius: values.get(4).copied().unwrap_or(0.0),
+            },
+            ("capsule", 5) => SdfPrimitive::Capsule {start: point(0), end: point(2), radius: values[4]},
+            ("circle" | "box" | "capsule", count) => return Err(format!("\"{}\" has {} numbers, expected circle x y radius, box x y half_width half_height [corner_radius] or capsule x1 y1 x2 y2 radius", line, count)),
+            _ => return Err(format!("Unknown part \"{}\", expected circle, box or capsule", line)),
+        };
+        Ok(Self {primitive, cut})
+    }
+
+    fn distance(&self, p: Vec2, s: f32) -> f32 {
+        match self.primitive {
+            SdfPrimitive::Circle {center, radius} => (p - center * s).len() - radius * s,
+            SdfPrimitive::Box {center, half_width, half_height, corner_radius} => {
+                rounded_rectangle_sdf(p - center * s, half_width * s, half_height * s, corner_radius * s)
+            },
+            SdfPrimitive::Capsule {start, end, radius} => {
+                let (start, end): (Vec2, Vec2) = (start * s, end * s);
+                let axis: Vec2 = end - start;
+                let t: f32 = ((p - start).dot(axis) / axis.dot(axis).max(f32::EPSILON)).clamp(0.0, 1.0);
+                (p - start - axis * t).len() - radius * s
+            },
+        }
+    }
+}
+
+// union of the parts with the cut ones subtracted, in order
+fn custom_sdf(parts: &[SdfPart], p: Vec2, s: f32) -> f32 {
+    parts.iter().fold(f32::INFINITY, |distance, part| {
+        if part.cut {
+            distance.max(-part.distance(p, s))
+        } else {
+            distance.min(part.distance(p, s))
+        }
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContainerShape {
+    // the window rectangle with its per-side constraint, bounce and temperature settings
+    Window,
+    Circle,
+    RoundedRectangle,
+    Annulus,
+    Custom,
+}
+
+impl ContainerShape {
+    pub const ALL: [Self; 5] = [
+        Self::Window,
+        Self::Circle,
+        Self::RoundedRectangle,
+        Self::Annulus,
+        Self::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Window => "Window",
+            Self::Circle => "Circle",
+            Self::RoundedRectangle => "Rounded rectangle",
+            Self::Annulus => "Annulus",
+            Self::Custom => "Custom SDF",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MotionStep {
+    Hold,
+    // moves out along the direction (degrees, 90 is down) and back
+    Shake(f32),
+    TurnClockwise,
+    TurnCounterClockwise,
+}
+
+impl MotionStep {
+    // parses sequences like "cw ccw" or "0, 180, hold"
+    pub fn parse_sequence(sequence: &str) -> Result<Vec<Self>, String> {
+        sequence
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .map(|token| match token.to_lowercase().as_str() {
+                "hold" => Ok(Self::Hold),
+                "cw" => Ok(Self::TurnClockwise),
+                "ccw" => Ok(Self::TurnCounterClockwise),
+                direction => direction.parse::<f32>()
+                    .map(Self::Shake)
+                    .map_err(|_| format!("Unknown step \"{}\", expected hold, cw, ccw or a shake direction in degrees", token)),
+            })
+            .collect()
+    }
+}
+
+// kinematic motion of the container, one step of the sequence per beat
+pub struct ContainerMotion {
+    pub enabled: bool,
+    pub bpm: f32,
+    // relative to the container scale
+    pub shake_amplitude: f32,
+    // degrees per turn step
+    pub turn_angle: f32,
+    pub sequence: String,
+    pub steps: Vec<MotionStep>,
+    pub sequence_error: String,
+}
+
+impl ContainerMotion {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            bpm: 60.0,
+            shake_amplitude: 0.05,
+            turn_angle: 45.0,
+            sequence: "cw ccw".to_string(),
+            steps: vec![MotionStep::TurnClockwise, MotionStep::TurnCounterClockwise],
+            sequence_error: String::new(),
+        }
+    }
+
+    pub fn set_sequence(&mut self, sequence: &str) {
+        self.sequence = sequence.to_string();
+        self.reload_sequence();
+    }
+
+    // keeps the last valid steps when the sequence doesn't parse
+    pub fn reload_sequence(&mut self) {
+        match MotionStep::parse_sequence(&self.sequence) {
+            Ok(steps) => {
+                self.steps = steps;
+                self.sequence_error.clear();
+            },
+            Err(error) => self.sequence_error = error,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.enabled && !self.steps.is_empty() && self.bpm > 0.0
+    }
+
+    // furthest the container gets moved away from the window center, relative to the container scale
+    fn max_shake(&self) -> f32 {
+        if self.steps.iter().any(|step| matches!(step, MotionStep::Shake(_))) {
+            self.shake_amplitude.abs()
+        } else {
+            0.0
+        }
+    }
+
+    fn turns(&self) -> bool {
+        self.turn_angle != 0.0 && self.steps.iter().any(|step| matches!(step, MotionStep::TurnClockwise | MotionStep::TurnCounterClockwise))
+    }
+
+    // rotation of a single step at the given fraction of its beat
+    fn turn(&self, step: MotionStep, fraction: f32) -> f32 {
+        let sign: f32 = match step {
+            MotionStep::TurnClockwise => 1.0,
+            MotionStep::TurnCounterClockwise => -1.0,
+            _ => 0.0,
+        };
+        // eases in and out so the direction changes smoothly between steps
+        sign * self.turn_angle.to_radians() * (1.0 - (PI * fraction).cos()) * 0.5
+    }
+
+    // offset, angle, velocity and angular velocity at the given time
+    fn state(&self, time: f32, scale: f32) -> (Vec2, f32, Vec2, f32) {
+        let beat: f32 = 60.0 / self.bpm;
+        let beats: f32 = time.max(0.0) / beat;
+        let step_index: usize = beats.floor() as usize % self.steps.len();
+        let fraction: f32 = beats.fract();
+        let step: MotionStep = self.steps[step_index];
+
+        // rotation adds up over every finished step
+        let cycle_turn: f32 = self.steps.iter().map(|&step| self.turn(step, 1.0)).sum();
+        let mut angle: f32 = (beats / self.steps.len() as f32).floor() * cycle_turn;
+        angle += self.steps[..step_index].iter().map(|&step| self.turn(step, 1.0)).sum::<f32>();
+        angle += self.turn(step, fraction);
+        let angular_velocity: f32 = self.turn(step, 1.0) * 0.5 * PI * (PI * fraction).sin() / beat;
+
+        let (offset, velocity) = match step {
+            MotionStep::Shake(direction) => {
+                let direction: Vec2 = Vec2 {
+                    x: direction.to_radians().cos(),
+                    y: direction.to_radians().sin(),
+                };
+                let amplitude: f32 = self.shake_amplitude * scale;
+                // starts and ends at rest like the turns
+                (
+                    direction * amplitude * (1.0 - (TAU * fraction).cos()) * 0.5,
+                    direction * amplitude * PI * (TAU * fraction).sin() / beat,
+                )
+            },
+            _ => (Vec2::zero(), Vec2::zero()),
+        };
+        (offset, angle, velocity, angular_velocity)
+    }
+}
+
+pub struct Container {
+    pub shape: ContainerShape,
+
+    // sizes are relative to the container scale so containers keep fitting the window
+    pub radius: f32,
+    pub inner_radius: f32,
+    pub half_width: f32,
+    pub half_height: f32,
+    pub corner_radius: f32,
+    // description of the custom container, parsed into custom_parts by reload_custom_sdf
+    pub custom_sdf: String,
+    pub custom_parts: Vec<SdfPart>,
+    pub custom_sdf_error: String,
+
+    // walls of shapes other than the window (or of the window while it moves)
+    pub bounce: bool,
+    pub wall_heat: WallHeat,
+    // fraction of the velocity along the wall (relative to it) removed on contact,
+    // lets turning walls drag objects along
+    pub wall_friction: f32,
+
+    pub motion: ContainerMotion,
+    // the window in the app, the container is centered in it and scaled to its shorter side
+    pub world_size: Vec2,
+    // current transform and its rate of change, set by update
+    offset: Vec2,
+    angle: f32,
+    velocity: Vec2,
+    angular_velocity: f32,
+}
+
+impl Container {
+    pub fn new(world_size: Vec2) -> Self {
+        Self {
+            shape: ContainerShape::Window,
+
+            radius: 0.9,
+            inner_radius: 0.3,
+            half_width: 1.5,
+            half_height: 0.8,
+            corner_radius: 0.2,
+            custom_sdf: CUSTOM_SDFS[0].1.to_string(),
+            custom_parts: SdfPart::parse_all(CUSTOM_SDFS[0].1).unwrap(),
+            custom_sdf_error: String::new(),
+
+            bounce: true,
+            wall_heat: WallHeat::default(),
+            wall_friction: 0.0,
+
+            motion: ContainerMotion::default(),
+            world_size,
+            offset: Vec2::zero(),
+            angle: 0.0,
+            velocity: Vec2::zero(),
+            angular_velocity: 0.0,
+        }
+    }
+
+    pub fn set_custom_sdf(&mut self, source: &str) {
+        self.custom_sdf = source.to_string();
+        self.reload_custom_sdf();
+    }
+
+    // keeps the last valid parts when the description doesn't parse
+    pub fn reload_custom_sdf(&mut self) {
+        match SdfPart::parse_all(&self.custom_sdf) {
+            Ok(parts) => {
+                self.custom_parts = parts;
+                self.custom_sdf_error.clear();
+            },
+            Err(error) => self.custom_sdf_error = error,
+        }
+    }
+
+    // the resting window is handled by the per-side settings instead of the signed distance
+    pub fn is_window(&self) -> bool {
+        self.shape == ContainerShape::Window && !self.motion.is_active()
+    }
+
+    pub fn update(&mut self, time: f32) {
+        (self.offset, self.angle, self.velocity, self.angular_velocity) = if self.motion.is_active() {
+            self.motion.state(time, self.scale())
+        } else {
+            (Vec2::zero(), 0.0, Vec2::zero(), 0.0)
+        };
+    }
+
+    // velocity of the container wall at the given position
+    pub fn wall_velocity(&self, position: Vec2) -> Vec2 {
+        let arm: Vec2 = position - self.center() - self.offset;
+        self.velocity + Vec2 {x: -arm.y, y: arm.x} * self.angular_velocity
+    }
+
+    fn to_local(&self, position: Vec2) -> Vec2 {
+        (position - self.center() - self.offset).rotated(-self.angle)
+    }
+
+    fn to_world(&self, local: Vec2) -> Vec2 {
+        self.center() + self.offset + local.rotated(self.angle)
+    }
+
+    fn center(&self) -> Vec2 {
+        self.world_size * 0.5
+    }
+
+    fn scale(&self) -> f32 {
+        self.world_size.x.min(self.world_size.y) * 0.5
+    }
+
+    // signed distance to the container wall, negative inside
+    pub fn distance(&self, position: Vec2) -> f32 {
+        self.local_distance(self.to_local(position))
+    }
+
+    fn local_distance(&self, p: Vec2) -> f32 {
+        let s: f32 = self.scale();
+        match self.shape {
+            ContainerShape::Window => {
+                let (half_width, half_height): (f32, f32) = self.window_half_size();
+                rounded_rectangle_sdf(p, half_width, half_height, 0.0)
+            },
+            ContainerShape::Circle => p.len() - self.radius * s,
+            ContainerShape::RoundedRectangle => rounded_rectangle_sdf(p, self.half_width * s, self.half_height * s, self.corner_radius * s),
+            ContainerShape::Annulus => (p.len() - self.radius * s).max(self.inner_radius * s - p.len()),
+            ContainerShape::Custom => custom_sdf(&self.custom_parts, p, s),
+        }
+    }
+
+    // the moving window shrinks so that everything it sweeps over stays on screen, where the
+    // broadphase grid and the out of bounds removal expect the objects to be
+    fn window_half_size(&self) -> (f32, f32) {
+        let shake: f32 = self.motion.max_shake() * self.scale();
+        let half_width: f32 = (self.world_size.x * 0.5 - shake).max(1.0);
+        let half_height: f32 = (self.world_size.y * 0.5 - shake).max(1.0);
+        if self.motion.turns() {
+            // any angle fits once the corners stay within the largest circle on screen
+            let fit: f32 = ((self.scale() - shake).max(1.0) / half_width.hypot(half_height)).min(1.0);
+            (half_width * fit, half_height * fit)
+        } else {
+            (half_width, half_height)
+        }
+    }
+
+    // outward wall normal from the gradient of the distance, works for any shape
+    pub fn normal(&self, position: Vec2) -> Vec2 {
+        const EPSILON: f32 = 0.5;
+        let dx: Vec2 = Vec2 {x: EPSILON, y: 0.0};
+        let dy: Vec2 = Vec2 {x: 0.0, y: EPSILON};
+        let gradient: Vec2 = Vec2 {
+            x: self.distance(position + dx) - self.distance(position - dx),
+            y: self.distance(position + dy) - self.distance(position - dy),
+        };
+        let len: f32 = gradient.len();
+        if len > 0.0 {
+            gradient / len
+        } else {
+            Vec2::zero()
+        }
+    }
+
+    pub fn draw(&self) {
+        for line in self.outline() {
+            for segment in line.windows(2) {
+                draw_line(segment[0].x, segment[0].y, segment[1].x, segment[1].y, 2.0, GRAY);
+            }
+        }
+    }
+
+    fn outline(&self) -> Vec<Vec<Vec2>> {
+        let s: f32 = self.scale();
+        let local_outlines: Vec<Vec<Vec2>> = match self.shape {
+            ContainerShape::Window if self.is_window() => vec![],
+            ContainerShape::Circle => vec![circle_outline(self.radius * s)],
+            ContainerShape::Annulus => vec![circle_outline(self.radius * s), circle_outline(self.inner_radius * s)],
+            _ => vec![self.traced_outline()],
+        };
+        local_outlines.into_iter()
+            .map(|outline| outline.into_iter().map(|local| self.to_world(local)).collect())
+            .collect()
+    }
+
+    // sphere traces rays from the container center to the wall,
+    // which only finds the whole outline of containers star shaped around the center
+    fn traced_outline(&self) -> Vec<Vec2> {
+        (0..=OUTLINE_SEGMENTS).map(|i| {
+            let angle: f32 = i as f32 / OUTLINE_SEGMENTS as f32 * TAU;
+            let direction: Vec2 = Vec2 {x: angle.cos(), y: angle.sin()};
+            let mut t: f32 = 0.0;
+            for _ in 0..OUTLINE_TRACE_STEPS {
+                let distance: f32 = self.local_distance(direction * t);
+                if distance.abs() < 0.25 {
+                    break;
+                }
+                t -= distance;
+            }
+            direction * t
+        }).collect()
+    }
+}
+
+fn circle_outline(radius: f32) -> Vec<Vec2> {
+    (0..=OUTLINE_SEGMENTS).map(|i| {
+        let angle: f32 = i as f32 / OUTLINE_SEGMENTS as f32 * TAU;
+        Vec2 {x: angle.cos(), y: angle.sin()} * radius
+    }).collect()
+}
+
+pub fn rounded_rectangle_sdf(p: Vec2, half_width: f32, half_height: f32, corner_radius: f32) -> f32 {
+    let corner_radius: f32 = corner_radius.min(half_width).min(half_height);
+    let qx: f32 = p.x.abs() - (half_width - corner_radius);
+    let qy: f32 = p.y.abs() - (half_height - corner_radius);
+    let outside: f32 = Vec2 {x: qx.max(0.0), y: qy.max(0.0)}.len();
+    let inside: f32 = qx.max(qy).min(0.0);
+    outside + inside - corner_radius
+}
@@ -1,10 +1,44 @@
 
 use macroquad::prelude::*;
-
-use crate::solver::Solver;
+use ::rand::{thread_rng, Rng};
+
+use crate::solver::{Solver, Broadphase};
+use crate::container::ContainerShape;
+use crate::gravity::{GravityWell, Falloff};
+use crate::rigidbody::RigidShape;
+use crate::shape::ShapeKind;
+use crate::thermal::WallHeat;
+use crate::timeline::{Timeline, Track, Parameter, Curve};
+use crate::batch::CircleBatch;
+use crate::benchmark::{BenchmarkResult, FluidCheckResult};
+use crate::color_map::{ColorMapContext, ColorMapSource, ColorMapPalette};
 use crate::shaders::{ShaderContext, FIRE_FRAGMENT_SHADER, WATER_FRAGMENT_SHADER, DENSITY_FRAGMENT_SHADER};
 use crate::vector;
 
+// radius and count of every group of objects the density preset spawns, also used by the density benchmark
+pub const DENSITY_PRESET_SPAWNS: [(f32, usize); 3] = [(20.0, 100), (10.0, 400), (5.0, 1_000)];
+
+// column of water in the left third of the world, used by the dam break preset and the fluid check
+pub fn dam_break_scene(solver: &mut Solver) {
+    solver.fluid.enabled = true;
+    solver.materials.cohesion_enabled = true;
+    solver.spawn_material = WATER_MATERIAL;
+    solver.spawn_radius = 5.0;
+    let world_size: vector::Vec2 = solver.world_size;
+    solver.spawn_block(
+        vector::Vec2 {x: 0.0, y: world_size.y / 3.0},
+        vector::Vec2 {x: world_size.x / 3.0, y: world_size.y},
+    );
+}
+
+pub fn screen_size() -> vector::Vec2 {
+    vector::Vec2 {x: screen_width(), y: screen_height()}
+}
+
+// indices into the default materials
+const WATER_MATERIAL: u8 = 1;
+const GOO_MATERIAL: u8 = 3;
+
 pub struct Context {
     pub solver: Solver,
 
@@ -37,8 +71,30 @@ pub struct Context {
     pub shake_intensity: f32,
     pub shake_direction: f32,
 
+    // automatic spawning and shaking, per second of simulated time
+    pub spawn_rate: f32,
+    pub shake_rate: f32,
+    spawn_progress: f32,
+    shake_progress: f32,
+
+    // keyframed parameters
+    pub timeline: Timeline,
+
     // shaders
     pub shader_context: ShaderContext,
+
+    // cpu color mapping
+    pub color_map: ColorMapContext,
+
+    // rendering
+    pub batched_rendering: bool,
+    // line from the center to the rim of every circle, shows how it turns
+    pub show_rotation_markers: bool,
+    pub circle_batch: CircleBatch,
+
+    // results of the last benchmark run
+    pub benchmark_results: Vec<BenchmarkResult>,
+    pub fluid_check_result: Option<FluidCheckResult>,
     
     // presets
     pub current_preset_name: String,
@@ -47,7 +103,7 @@ pub struct Context {
 impl Context {
     pub fn default() -> Self {
         Self {
-            solver: Solver::new(),
+            solver: Solver::new(screen_size()),
 
             sfps_target: 60.0,
             sfps_target_enforced: !(cfg!(target_arch = "wasm32") && cfg!(target_os = "unknown")),
@@ -71,14 +127,30 @@ impl Context {
             shake_intensity: 100_000.0,
             shake_direction: 90.0,
 
+            spawn_rate: 0.0,
+            shake_rate: 0.0,
+            spawn_progress: 0.0,
+            shake_progress: 0.0,
+
+            timeline: Timeline::default(),
+
             shader_context: ShaderContext::default(),
 
+            color_map: ColorMapContext::default(),
+
+            batched_rendering: true,
+            show_rotation_markers: false,
+            circle_batch: CircleBatch::new(),
+
+            benchmark_results: vec![],
+            fluid_check_result: None,
+
             current_preset_name: "Default".to_string(),
         }
     }
 
     pub fn reset(&mut self) {
-        self.solver = Solver::new();
+        self.solver = Solver::new(screen_size());
         self.sfps_target = 60.0;
         self.sfps_target_enforced = !(cfg!(target_arch = "wasm32") && cfg!(target_os = "unknown"));
         self.sfps_min = 60.0;
@@ -92,11 +164,43 @@ impl Context {
         self.shake_auto_random = false;
         self.shake_intensity = 100_000.0;
         self.shake_direction = 90.0;
-        self.shader_context.material.delete();
+        self.spawn_rate = 0.0;
+        self.shake_rate = 0.0;
+        self.timeline = Timeline::default();
         self.shader_context = ShaderContext::default();
+        self.color_map = ColorMapContext::default();
+        self.batched_rendering = true;
+        self.show_rotation_markers = false;
         self.current_preset_name = "Default".to_string();
     }
 
+    pub fn shake(&mut self) {
+        self.solver.accelerate_all(self.shake_intensity, self.shake_direction.to_radians());
+        if self.shake_auto_random {
+            self.shake_direction = thread_rng().gen_range(0..360) as f32;
+        }
+    }
+
+    // plays the timeline and the automatic spawning and shaking, called once per simulation frame
+    pub fn automate(&mut self, dt: f32) {
+        for (parameter, value) in self.timeline.advance(dt) {
+            parameter.set(self, value);
+        }
+
+        self.spawn_progress += self.spawn_rate.max(0.0) * dt;
+        if self.spawn_progress >= 1.0 {
+            let count: f32 = self.spawn_progress.floor();
+            self.solver.spawn_count(count as usize);
+            self.spawn_progress -= count;
+        }
+
+        self.shake_progress += self.shake_rate.max(0.0) * dt;
+        if self.shake_progress >= 1.0 {
+            self.shake_progress = self.shake_progress.fract();
+            self.shake();
+        }
+    }
+
     pub fn stable_sixty_web_preset(&mut self) {
         self.reset();
         self.sfps_max_enforced = true;
@@ -110,17 +214,15 @@ impl Context {
     }
 
     pub fn fire_preset_one(&mut self) {
-        self.solver = Solver::new();
+        self.solver = Solver::new(screen_size());
         self.solver.accelerate_on_temperature = true;
         self.solver.temperature_acceleration_power = 2.0;
         self.solver.gravity.y = 9_500.0;
-        self.solver.apply_temperature_bottom = 0.1;
+        self.solver.wall_heat_bottom = WallHeat::heat_flux(5_000.0);
         self.solver.min_object_count = 1_500;
         self.solver.min_object_count_enforced = true;
-        self.shader_context.material.delete();
         self.shader_context = ShaderContext::default();
         self.shader_context.use_shaders = true;
-        self.shader_context.use_uniform_temperature = true;
         self.shader_context.fragment_shader = FIRE_FRAGMENT_SHADER.to_string();
         self.shader_context.reload_shaders();
         self.current_preset_name = "Fire 1".to_string();
@@ -134,7 +236,7 @@ impl Context {
     }
 
     pub fn rain_preset(&mut self) {
-        self.solver = Solver::new();
+        self.solver = Solver::new(screen_size());
         self.solver.min_object_count = 30;
         self.solver.min_object_count_enforced = true;
         self.solver.apply_constraint_bottom = false;
@@ -142,9 +244,11 @@ impl Context {
         self.solver.apply_constraint_left = false;
         self.solver.apply_constraint_right = false;
         self.solver.stabilize_on_oob = false;
+        self.solver.broadphase = Broadphase::SpatialHash;
+        // droplets beading into each other
+        self.solver.spawn_material = WATER_MATERIAL;
+        self.solver.materials.cohesion_enabled = true;
         self.shader_context.use_shaders = true;
-        self.shader_context.use_uniform_pos_curr = true;
-        self.shader_context.use_uniform_pos_old = true;
         self.shader_context.fragment_shader = WATER_FRAGMENT_SHADER.to_string();
         self.shader_context.reload_shaders();
         self.current_preset_name = "Rain".to_string();
@@ -159,6 +263,12 @@ impl Context {
         self.solver.apply_constraint_right = false;
         self.solver.spawn_radius = 50.0;
         self.solver.gravity = vector::Vec2::zero();
+        // spin from glancing hits
+        self.solver.friction = 0.5;
+        self.show_rotation_markers = true;
+        // pins knocked off screen keep colliding until they leave the kill region
+        self.solver.broadphase = Broadphase::SpatialHash;
+        self.solver.kill_region_margin = 1_000.0;
 
         let mid_y = screen_height() * 0.5;
         let horizontal_spacing = 60.0 * 3f32.sqrt();
@@ -182,18 +292,249 @@ impl Context {
         self.solver.spawn(vector::Vec2{x: screen_width() - 200.0 - horizontal_spacing*3.0, y: mid_y});
     }
 
-    pub fn density_preset(&mut self) {
+    pub fn circle_container_preset(&mut self) {
+        self.reset();
+        self.solver.container.shape = ContainerShape::Circle;
+        self.solver.spawn_color_random_hue = true;
+        self.solver.spawn_count(500);
+        self.current_preset_name = "Circle container".to_string();
+    }
+
+    pub fn mixer_preset(&mut self) {
+        self.reset();
+        self.solver.container.shape = ContainerShape::RoundedRectangle;
+        self.solver.container.half_width = 0.8;
+        self.solver.container.half_height = 0.8;
+        self.solver.container.corner_radius = 0.1;
+        self.solver.container.wall_friction = 0.1;
+        self.solver.container.motion.enabled = true;
+        self.solver.container.motion.bpm = 40.0;
+        self.solver.container.motion.turn_angle = 90.0;
+        self.solver.container.motion.set_sequence("cw ccw");
+        self.solver.spawn_color_random_hue = true;
+        self.solver.spawn_count(800);
+        self.current_preset_name = "Mixer".to_string();
+    }
+
+    pub fn auto_shake_preset(&mut self) {
         self.reset();
-        self.solver.spawn_radius = 20.0;
-        self.solver.spawn_count(100);
-        self.solver.spawn_radius = 10.0;
-        self.solver.spawn_count(400);
+        self.solver.spawn_count(1_000);
+
+        // a shake every half a second after settling for two seconds, turning around once per loop
+        let mut shake_rate: Track = Track::new(Parameter::ShakeRate);
+        shake_rate.add(0.0, 0.0, Curve::Step);
+        shake_rate.add(2.0, 2.0, Curve::Step);
+        let mut shake_direction: Track = Track::new(Parameter::ShakeDirection);
+        shake_direction.add(2.0, 0.0, Curve::Linear);
+        shake_direction.add(10.0, 360.0, Curve::Linear);
+        self.timeline.tracks = vec![shake_rate, shake_direction];
+        self.timeline.playing = true;
+        self.current_preset_name = "Auto shake".to_string();
+    }
+
+    pub fn orbit_preset(&mut self) {
+        self.reset();
+        self.solver.gravity = vector::Vec2::zero();
+        self.solver.apply_constraint_bottom = false;
+        self.solver.apply_constraint_top = false;
+        self.solver.apply_constraint_left = false;
+        self.solver.apply_constraint_right = false;
+        self.solver.stabilize_on_oob = false;
         self.solver.spawn_radius = 5.0;
-        self.solver.spawn_count(1000);
-        self.shader_context.material.delete();
+        self.solver.spawn_color_random_hue = true;
+
+        let center: vector::Vec2 = vector::Vec2 {x: screen_width() * 0.5, y: screen_height() * 0.5};
+        let well: GravityWell = GravityWell::new(center);
+        self.solver.gravity_wells.push(well);
+
+        // rings of objects with the velocity of a circular orbit, verlet velocity is the offset per substep
+        let dt: f32 = 1.0 / (self.sfps_target as f32 * self.sim_substeps as f32);
+        let spacing: f32 = self.solver.spawn_radius * 3.0;
+        let max_distance: f32 = screen_width().min(screen_height()) * 0.5 - spacing;
+        let mut distance: f32 = well.reference_distance * 1.5;
+        while distance < max_distance {
+            let speed: f32 = (well.strength * well.reference_distance * well.reference_distance / distance).sqrt();
+            let count: usize = (std::f32::consts::TAU * distance / spacing) as usize;
+            for i in 0..count {
+                let angle: f32 = std::f32::consts::TAU * i as f32 / count as f32;
+                let direction: vector::Vec2 = vector::Vec2 {x: angle.cos(), y: angle.sin()};
+                let tangent: vector::Vec2 = vector::Vec2 {x: -direction.y, y: direction.x};
+                let position: vector::Vec2 = center + direction * distance;
+                self.solver.spawn(position);
+                if let Some(position_old) = self.solver.verlet_objects.position_old.last_mut() {
+                    *position_old = position - tangent * speed * dt;
+                }
+            }
+            distance += spacing * 2.0;
+        }
+        self.current_preset_name = "Orbit".to_string();
+    }
+
+    pub fn attractor_preset(&mut self) {
+        self.reset();
+        self.solver.gravity = vector::Vec2::zero();
+        self.solver.spawn_color_random_hue = true;
+        let mut well: GravityWell = GravityWell::new(vector::Vec2 {x: screen_width() * 0.5, y: screen_height() * 0.5});
+        well.falloff = Falloff::Constant;
+        self.solver.gravity_wells.push(well);
+        self.solver.spawn_count(1_000);
+        self.current_preset_name = "Zero-g attractor".to_string();
+    }
+
+    pub fn accretion_preset(&mut self) {
+        self.reset();
+        self.solver.gravity = vector::Vec2::zero();
+        self.solver.n_body.enabled = true;
+        self.solver.spawn_radius = 3.0;
+        self.solver.spawn_color_random_hue = true;
+        self.solver.spawn_count(2_000);
+
+        // slow rotation around the center so the cloud forms a few bodies instead of a single one
+        let center: vector::Vec2 = vector::Vec2 {x: screen_width() * 0.5, y: screen_height() * 0.5};
+        let dt: f32 = 1.0 / (self.sfps_target as f32 * self.sim_substeps as f32);
+        let objects = &mut self.solver.verlet_objects;
+        for (position_current, position_old) in objects.position_current.iter().zip(objects.position_old.iter_mut()) {
+            let offset: vector::Vec2 = *position_current - center;
+            let tangent: vector::Vec2 = vector::Vec2 {x: -offset.y, y: offset.x};
+            *position_old = *position_current - tangent * 0.2 * dt;
+        }
+        self.current_preset_name = "Accretion".to_string();
+    }
+
+    pub fn dam_break_preset(&mut self) {
+        self.reset();
+        dam_break_scene(&mut self.solver);
+        self.color_map.source = ColorMapSource::Speed;
+        self.current_preset_name = "Dam break".to_string();
+    }
+
+    pub fn jelly_preset(&mut self) {
+        self.reset();
+        self.solver.spawn_radius = 6.0;
+        self.solver.spawn_color_random_hue = true;
+        self.solver.spawn_block(
+            vector::Vec2 {x: 0.0, y: screen_height() * 0.75},
+            vector::Vec2 {x: screen_width(), y: screen_height()},
+        );
+        for i in 0..4 {
+            let x: f32 = screen_width() * (i as f32 + 0.5) / 4.0;
+            self.solver.spawn_soft_body(vector::Vec2 {x, y: self.solver.soft_bodies.spawn_radius + 20.0});
+        }
+        self.current_preset_name = "Jelly".to_string();
+    }
+
+    pub fn tumbling_shapes_preset(&mut self) {
+        self.reset();
+        self.solver.spawn_radius = 6.0;
+        self.solver.spawn_color_random_hue = true;
+        self.solver.spawn_block(
+            vector::Vec2 {x: 0.0, y: screen_height() * 0.8},
+            vector::Vec2 {x: screen_width(), y: screen_height()},
+        );
+        for (i, shape) in RigidShape::ALL.into_iter().enumerate() {
+            self.solver.rigid_bodies.spawn_shape = shape;
+            let x: f32 = screen_width() * (i as f32 + 0.5) / RigidShape::ALL.len() as f32;
+            self.solver.spawn_rigid_body(vector::Vec2 {x, y: screen_height() * 0.25});
+        }
+        self.solver.rigid_bodies.spawn_shape = RigidShape::Box;
+        self.current_preset_name = "Tumbling shapes".to_string();
+    }
+
+    pub fn shape_pile_preset(&mut self) {
+        self.reset();
+        self.solver.spawn_radius = 15.0;
+        self.solver.spawn_color_random_hue = true;
+        self.solver.spawn_polygon_random = true;
+        for shape in ShapeKind::ALL {
+            self.solver.spawn_shape = shape;
+            self.solver.spawn_count(50);
+        }
+        self.current_preset_name = "Shape pile".to_string();
+    }
+
+    pub fn melting_ice_preset(&mut self) {
+        self.reset();
+        self.solver.phase_changes.enabled = true;
+        self.solver.spawn_material = WATER_MATERIAL;
+        self.solver.spawn_radius = 6.0;
+        // every wall is a hot plate while the air stays cool, so the steam condenses and rains back down,
+        // with little convection the cube melts from where it touches the water and the floor
+        let hot_wall: WallHeat = WallHeat::fixed_temperature(2.5);
+        self.solver.convection = 1.0;
+        self.solver.wall_heat_bottom = hot_wall;
+        self.solver.wall_heat_top = hot_wall;
+        self.solver.wall_heat_left = hot_wall;
+        self.solver.wall_heat_right = hot_wall;
+        // the cube spawns frozen and bonds together on the first substep
+        self.solver.ambient_temperature = -1.0;
+        let half_size: f32 = screen_height().min(screen_width()) * 0.2;
+        self.solver.spawn_block(
+            vector::Vec2 {x: screen_width() * 0.5 - half_size, y: screen_height() - half_size * 2.0},
+            vector::Vec2 {x: screen_width() * 0.5 + half_size, y: screen_height()},
+        );
+        self.solver.ambient_temperature = -0.4;
+        self.color_map.source = ColorMapSource::Temperature;
+        self.color_map.palette = ColorMapPalette::Inferno;
+        self.current_preset_name = "Melting ice".to_string();
+    }
+
+    pub fn sticky_goo_preset(&mut self) {
+        self.reset();
+        self.solver.spawn_material = GOO_MATERIAL;
+        self.solver.materials.cohesion_enabled = true;
+        self.solver.materials.adhesion_enabled = true;
+        self.solver.spawn_color_random_hue = true;
+        self.solver.spawn_count(600);
+
+        // gravity up for a second and a half to throw the goo at the ceiling, then back down
+        let mut gravity: Track = Track::new(Parameter::GravityY);
+        gravity.add(0.0, -1_000.0, Curve::Step);
+        gravity.add(1.5, 1_000.0, Curve::Step);
+        self.timeline.tracks = vec![gravity];
+        self.timeline.looping = false;
+        self.timeline.length = 1.5;
+        self.timeline.playing = true;
+        self.current_preset_name = "Sticky goo".to_string();
+    }
+
+    fn charge_color_map(&mut self) {
+        self.color_map.source = ColorMapSource::Charge;
+        self.color_map.palette = ColorMapPalette::Gradient;
+    }
+
+    pub fn ionic_crystal_preset(&mut self) {
+        self.reset();
+        self.solver.electrostatics.enabled = true;
+        self.solver.spawn_radius = 8.0;
+        self.solver.spawn_charge = 10.0;
+        self.solver.spawn_charge_alternate = true;
+        self.solver.spawn_count(800);
+        self.charge_color_map();
+        self.current_preset_name = "Ionic crystal".to_string();
+    }
+
+    pub fn plasma_preset(&mut self) {
+        self.reset();
+        self.solver.gravity = vector::Vec2::zero();
+        self.solver.wrap_x = true;
+        self.solver.wrap_y = true;
+        self.solver.electrostatics.enabled = true;
+        self.solver.spawn_radius = 4.0;
+        self.solver.spawn_charge = 5.0;
+        self.solver.spawn_charge_alternate = true;
+        self.solver.spawn_count(1_500);
+        self.charge_color_map();
+        self.current_preset_name = "Plasma".to_string();
+    }
+
+    pub fn density_preset(&mut self) {
+        self.reset();
+        for (radius, count) in DENSITY_PRESET_SPAWNS {
+            self.solver.spawn_radius = radius;
+            self.solver.spawn_count(count);
+        }
         self.shader_context = ShaderContext::default();
         self.shader_context.use_shaders = true;
-        self.shader_context.use_uniform_radius = true;
         self.shader_context.fragment_shader = DENSITY_FRAGMENT_SHADER.to_string();
         self.shader_context.reload_shaders();
         self.current_preset_name = "Density".to_string();
@@ -0,0 +1,89 @@
+use std::f32::consts::PI;
+use crate::vector::Vec2;
+
+// position based fluids (Macklin and Müller 2013): objects of fluid materials are moved so that
+// the density around every one of them stays at the rest density of tightly packed objects,
+// run alongside the collisions on the positions the last integration predicted,
+// surface tension comes from the cohesion of the fluid materials
+pub struct Fluid {
+    pub enabled: bool,
+    // density constraint passes per substep
+    pub iterations: usize,
+    // smoothing radius as a multiple of the object diameter
+    pub kernel_factor: f32,
+    // softens the constraint so it doesn't divide by almost zero for objects with few neighbours
+    pub relaxation: f32,
+    // xsph viscosity, how much objects take on the average velocity of their neighbours
+    pub viscosity: f32,
+
+    // pairs of fluid objects within the smoothing radius, rebuilt every substep
+    pub pairs: Vec<(usize, usize)>,
+}
+
+impl Fluid {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            iterations: 3,
+            kernel_factor: 2.0,
+            relaxation: 0.01,
+            viscosity: 0.1,
+            pairs: vec![],
+        }
+    }
+}
+
+// 2D poly6 kernel, used for the density
+pub fn poly6(distance_squared: f32, h: f32) -> f32 {
+    let h_squared: f32 = h * h;
+    if distance_squared >= h_squared {
+        return 0.0;
+    }
+    let difference: f32 = h_squared - distance_squared;
+    4.0 / (PI * h_squared.powi(4)) * difference * difference * difference
+}
+
+// gradient of the 2D spiky kernel with respect to the first position, offset is from the
+// second position to the first one, used for the pressure as it doesn't vanish up close
+pub fn spiky_gradient(offset: Vec2, h: f32) -> Vec2 {
+    let distance: f32 = offset.len();
+    if distance >= h || distance <= f32::EPSILON {
+        return Vec2::zero();
+    }
+    offset * (-30.0 / (PI * h.powi(5)) * (h - distance) * (h - distance) / distance)
+}
+
+// density of a hexagonal packing of objects touching each other, counting every neighbour
+// within the smoothing radius, the density the constraint keeps objects at
+pub fn rest_density(spacing: f32, h: f32) -> f32 {
+    let reach: i32 = (h / spacing).ceil() as i32 + 1;
+    let row_height: f32 = spacing * 3f32.sqrt() * 0.5;
+    let mut density: f32 = 0.0;
+    for row in -reach..=reach {
+        let shift: f32 = if row % 2 == 0 {0.0} else {spacing * 0.5};
+        for column in -reach..=reach {
+            let offset: Vec2 = Vec2 {x: column as f32 * spacing + shift, y: row as f32 * row_height};
+            density += poly6(offset.x * offset.x + offset.y * offset.y, h);
+        }
+    }
+    density
+}
+
+// spread of the top surface of the given objects, the standard deviation of the highest
+// object in every column of the given width, zero for a perfectly flat surface
+pub fn surface_flatness(positions: impl Iterator<Item = Vec2>, column_width: f32) -> f32 {
+    let mut tops: Vec<(i64, f32)> = vec![];
+    for position in positions {
+        let column: i64 = (position.x / column_width).floor() as i64;
+        match tops.iter_mut().find(|(top_column, _)| *top_column == column) {
+            Some((_, top)) => *top = top.min(position.y),
+            None => tops.push((column, position.y)),
+        }
+    }
+    if tops.is_empty() {
+        return 0.0;
+    }
+    let mean: f32 = tops.iter().map(|(_, top)| top).sum::<f32>() / tops.len() as f32;
+    let variance: f32 = tops.iter().map(|(_, top)| (top - mean) * (top - mean)).sum::<f32>() / tops.len() as f32;
+    variance.sqrt()
+}
@@ -0,0 +1,39 @@
+use crate::vector::Vec2;
+
+// coulomb interaction between charged objects, like charges repel and opposite ones attract
+#[derive(Clone, Copy)]
+pub struct Electrostatics {
+    pub enabled: bool,
+    pub coulomb_constant: f32,
+    // pairs further apart than this don't interact, the force fades out toward it
+    pub cutoff: f32,
+    // added to the distance so overlapping opposite charges don't get infinite forces
+    pub softening: f32,
+    // uniform field pushing positive charges along it and negative ones against it
+    pub electric_field: Vec2,
+}
+
+impl Electrostatics {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            coulomb_constant: 1_000_000.0,
+            cutoff: 100.0,
+            softening: 2.0,
+            electric_field: Vec2::zero(),
+        }
+    }
+
+    // force on the first object, offset points from the first object to the second one
+    pub fn force(&self, charge_1: f32, charge_2: f32, offset: Vec2) -> Vec2 {
+        let distance_squared: f32 = offset.x * offset.x + offset.y * offset.y;
+        let cutoff_squared: f32 = self.cutoff * self.cutoff;
+        if distance_squared >= cutoff_squared || charge_1 == 0.0 || charge_2 == 0.0 {
+            return Vec2::zero();
+        }
+        let softened: f32 = distance_squared + self.softening * self.softening;
+        // smooth taper to zero at the cutoff so objects crossing it don't get kicked
+        let taper: f32 = (1.0 - distance_squared / cutoff_squared).powi(2);
+        offset * (-self.coulomb_constant * charge_1 * charge_2 * taper / (softened * softened.sqrt()))
+    }
+}
@@ -0,0 +1,98 @@
+use macroquad::prelude::{draw_circle_lines, draw_line, draw_rectangle_lines, Color};
+use crate::vector::Vec2;
+
+const WELL_COLOR: Color = Color::new(1.0, 0.8, 0.2, 0.6);
+const ZONE_COLOR: Color = Color::new(0.3, 0.6, 1.0, 0.6);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Falloff {
+    Constant,
+    InverseSquare,
+}
+
+impl Falloff {
+    pub const ALL: [Self; 2] = [
+        Self::Constant,
+        Self::InverseSquare,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Constant => "Constant",
+            Self::InverseSquare => "Inverse square",
+        }
+    }
+}
+
+// pulls objects toward a point, or pushes them away with a negative strength
+#[derive(Clone, Copy)]
+pub struct GravityWell {
+    pub position: Vec2,
+    // acceleration at the reference distance
+    pub strength: f32,
+    pub falloff: Falloff,
+    // the inverse square falloff is capped at this distance so objects passing
+    // right through the center don't get flung away
+    pub reference_distance: f32,
+}
+
+impl GravityWell {
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            position,
+            strength: 1_000.0,
+            falloff: Falloff::InverseSquare,
+            reference_distance: 100.0,
+        }
+    }
+
+    pub fn acceleration(&self, position: Vec2) -> Vec2 {
+        let offset: Vec2 = self.position - position;
+        let distance: f32 = offset.len();
+        if distance <= f32::EPSILON {
+            return Vec2::zero();
+        }
+        let magnitude: f32 = match self.falloff {
+            Falloff::Constant => self.strength,
+            Falloff::InverseSquare => {
+                let ratio: f32 = self.reference_distance / distance.max(self.reference_distance);
+                self.strength * ratio * ratio
+            },
+        };
+        offset * (magnitude / distance)
+    }
+
+    pub fn draw(&self) {
+        let Vec2{x, y} = self.position;
+        draw_circle_lines(x, y, 8.0, 2.0, WELL_COLOR);
+        draw_line(x - 12.0, y, x + 12.0, y, 2.0, WELL_COLOR);
+        draw_line(x, y - 12.0, x, y + 12.0, 2.0, WELL_COLOR);
+        if self.falloff == Falloff::InverseSquare {
+            draw_circle_lines(x, y, self.reference_distance, 1.0, WELL_COLOR);
+        }
+    }
+}
+
+// rectangle with its own uniform gravity, replaces the global gravity for objects inside
+#[derive(Clone, Copy)]
+pub struct GravityZone {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub gravity: Vec2,
+}
+
+impl GravityZone {
+    pub fn contains(&self, position: Vec2) -> bool {
+        position.x >= self.min.x && position.x < self.max.x && position.y >= self.min.y && position.y < self.max.y
+    }
+
+    pub fn draw(&self) {
+        draw_rectangle_lines(self.min.x, self.min.y, self.max.x - self.min.x, self.max.y - self.min.y, 2.0, ZONE_COLOR);
+        let center: Vec2 = (self.min + self.max) * 0.5;
+        let len: f32 = self.gravity.len();
+        if len > 0.0 {
+            let tip: Vec2 = center + self.gravity * (40.0 / len);
+            draw_line(center.x, center.y, tip.x, tip.y, 2.0, ZONE_COLOR);
+        }
+    }
+}
@@ -0,0 +1,651 @@
+use crate::{vector::Vec2, verlet::VerletObjects};
+
+// objects outside of the grid don't belong to any cell
+const NO_CELL: usize = usize::MAX;
+
+// together with the cell itself these cover every neighbouring cell pair exactly once
+const HALF_NEIGHBOURHOOD: [(isize, isize); 4] = [(1, 0), (-1, 1), (0, 1), (1, 1)];
+
+// uniform grid stored as one flat index array sorted by cell (counting sort),
+// objects of cell c are object_indices[cell_start[c]..cell_start[c + 1]]
+pub struct CellGrid {
+    pub width: usize,
+    pub height: usize,
+    pub cell_size: f32,
+    // periodic axes, objects near one edge are neighbours of objects near the opposite edge
+    pub wrap_x: bool,
+    pub wrap_y: bool,
+    cell_width: f32,
+    cell_height: f32,
+    cell_start: Vec<usize>,
+    object_indices: Vec<usize>,
+    object_cells: Vec<usize>,
+}
+
+impl CellGrid {
+    pub fn new() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            cell_size: 1.0,
+            wrap_x: false,
+            wrap_y: false,
+            cell_width: 1.0,
+            cell_height: 1.0,
+            cell_start: vec![0],
+            object_indices: vec![],
+            object_cells: vec![],
+        }
+    }
+
+    pub fn build(&mut self, positions: &[Vec2], cell_size: f32, world_width: f32, world_height: f32) {
+        self.build_subset(positions, |_| true, cell_size, world_width, world_height);
+    }
+
+    // same as build but objects for which include returns false are left out of the grid
+    pub fn build_subset(&mut self, positions: &[Vec2], include: impl Fn(usize) -> bool, cell_size: f32, world_width: f32, world_height: f32) {
+        self.cell_size = cell_size;
+        (self.width, self.cell_width) = grid_axis(world_width, cell_size, self.wrap_x);
+        (self.height, self.cell_height) = grid_axis(world_height, cell_size, self.wrap_y);
+        let cell_count: usize = self.width * self.height;
+
+        // count objects per cell
+        self.cell_start.clear();
+        self.cell_start.resize(cell_count + 1, 0);
+        self.object_cells.clear();
+        for (i, position) in positions.iter().enumerate() {
+            let cell: usize = if include(i) {
+                self.cell_of(position.x, position.y)
+            } else {
+                NO_CELL
+            };
+            if cell != NO_CELL {
+                self.cell_start[cell + 1] += 1;
+            }
+            self.object_cells.push(cell);
+        }
+
+        // prefix sum turns counts into start offsets
+        for cell in 0..cell_count {
+            self.cell_start[cell + 1] += self.cell_start[cell];
+        }
+
+        // scatter, cell_fill temporarily holds the next free slot of each cell
+        let mut cell_fill: Vec<usize> = self.cell_start[..cell_count].to_vec();
+        self.object_indices.clear();
+        self.object_indices.resize(self.cell_start[cell_count], 0);
+        for (i, &cell) in self.object_cells.iter().enumerate() {
+            if cell != NO_CELL {
+                self.object_indices[cell_fill[cell]] = i;
+                cell_fill[cell] += 1;
+            }
+        }
+    }
+
+    pub fn cell_of(&self, x: f32, y: f32) -> usize {
+        let mut grid_x: f32 = (x / self.cell_width).floor();
+        let mut grid_y: f32 = (y / self.cell_height).floor();
+        if self.wrap_x {
+            grid_x = grid_x.rem_euclid(self.width as f32);
+        }
+        if self.wrap_y {
+            grid_y = grid_y.rem_euclid(self.height as f32);
+        }
+        if grid_x < 0.0 || grid_y < 0.0 || grid_x >= self.width as f32 || grid_y >= self.height as f32 {
+            return NO_CELL;
+        }
+        grid_y as usize * self.width + grid_x as usize
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> &[usize] {
+        let cell: usize = y * self.width + x;
+        &self.object_indices[self.cell_start[cell]..self.cell_start[cell + 1]]
+    }
+
+    // visits every object in the cells overlapping the given rectangle, on wrapped axes the
+    // rectangle continues across the seam, each cell is visited at most once
+    pub fn for_each_in_rect(&self, min: Vec2, max: Vec2, mut visit: impl FnMut(usize)) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let Some((y_start, y_end)) = rect_axis(min.y, max.y, self.cell_height, self.height, self.wrap_y) else {
+            return;
+        };
+        let Some((x_start, x_end)) = rect_axis(min.x, max.x, self.cell_width, self.width, self.wrap_x) else {
+            return;
+        };
+        for y in y_start..=y_end {
+            for x in x_start..=x_end {
+                for &obj_index in self.cell(x.rem_euclid(self.width as isize) as usize, y.rem_euclid(self.height as isize) as usize) {
+                    visit(obj_index);
+                }
+            }
+        }
+    }
+
+    // neighbouring cell in the given direction, across the seam on wrapped axes
+    fn neighbour(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        Some((
+            neighbour_axis(x, dx, self.width, self.wrap_x)?,
+            neighbour_axis(y, dy, self.height, self.wrap_y)?,
+        ))
+    }
+
+    // visits every pair of objects in the same or neighbouring cells exactly once
+    pub fn for_each_pair(&self, solve: impl FnMut(usize, usize)) {
+        self.for_each_pair_in_columns(0, self.width, solve);
+    }
+
+    // same as for_each_pair but only for cells in columns x_start..x_end, pairs reach
+    // one column to the left and one to the right of that range
+    pub fn for_each_pair_in_columns(&self, x_start: usize, x_end: usize, mut solve: impl FnMut(usize, usize)) {
+        for y in 0..self.height {
+            for x in x_start..x_end {
+                let cell: &[usize] = self.cell(x, y);
+                for (slot, &obj_index_1) in cell.iter().enumerate() {
+                    for &obj_index_2 in &cell[slot + 1..] {
+                        solve(obj_index_1, obj_index_2);
+                    }
+                }
+                for (dx, dy) in HALF_NEIGHBOURHOOD {
+                    if let Some((ox, oy)) = self.neighbour(x, y, dx, dy) {
+                        for &obj_index_1 in cell {
+                            for &obj_index_2 in self.cell(ox, oy) {
+                                solve(obj_index_1, obj_index_2);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // visits all 9 neighbouring cells of every cell, so every pair is visited twice (in both orders)
+    pub fn for_each_pair_full(&self, mut solve: impl FnMut(usize, usize)) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        if let Some((ox, oy)) = self.neighbour(x, y, dx, dy) {
+                            for &obj_index_1 in self.cell(x, y) {
+                                for &obj_index_2 in self.cell(ox, oy) {
+                                    if obj_index_1 != obj_index_2 {
+                                        solve(obj_index_1, obj_index_2);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // reorders the objects the grid was built from so that objects of the same cell
+    // are next to each other in memory, objects outside of the grid are moved to the end,
+    // returns the order for anything else holding object indices (see VerletObjects::permute)
+    pub fn reorder(&mut self, objects: &mut VerletObjects) -> Vec<usize> {
+        let mut order: Vec<usize> = self.object_indices.clone();
+        for (i, &cell) in self.object_cells.iter().enumerate() {
+            if cell == NO_CELL {
+                order.push(i);
+            }
+        }
+        objects.permute(&order);
+
+        for (slot, object_index) in self.object_indices.iter_mut().enumerate() {
+            *object_index = slot;
+        }
+        self.object_cells.clear();
+        order
+    }
+}
+
+// cell count and cell size along one axis, wrapped axes are split into whole cells
+// (slightly larger than cell_size) so that the last cell lines up with the first one
+fn grid_axis(world_size: f32, cell_size: f32, wrap: bool) -> (usize, f32) {
+    if wrap {
+        let count: usize = ((world_size / cell_size).floor() as usize).max(1);
+        (count, world_size / count as f32)
+    } else {
+        ((world_size / cell_size).ceil() as usize, cell_size)
+    }
+}
+
+// cell along an axis of the spatial hash, folded into the world on wrapped axes
+fn fold_cell(cell: i32, wrap_cells: i32) -> i32 {
+    if wrap_cells > 0 {
+        cell.rem_euclid(wrap_cells)
+    } else {
+        cell
+    }
+}
+
+// the cell and the cells on both sides of it, on wrapped axes with less than 3 cells these
+// fold onto each other and every cell is listed once so no pair is visited twice
+fn neighbour_cells(cell: i32, wrap_cells: i32) -> ([i32; 3], usize) {
+    let mut cells: [i32; 3] = [cell; 3];
+    let mut count: usize = 1;
+    for delta in [-1, 1] {
+        let neighbour: i32 = fold_cell(cell.wrapping_add(delta), wrap_cells);
+        if !cells[..count].contains(&neighbour) {
+            cells[count] = neighbour;
+            count += 1;
+        }
+    }
+    (cells, count)
+}
+
+// first and last cell a rectangle covers along an axis, unfolded on wrapped axes and
+// clamped to the grid on open ones, None if it misses the grid
+fn rect_axis(min: f32, max: f32, cell_size: f32, size: usize, wrap: bool) -> Option<(isize, isize)> {
+    let start: isize = (min / cell_size).floor() as isize;
+    let end: isize = (max / cell_size).floor() as isize;
+    if wrap {
+        Some((start, end.min(start + size as isize - 1)))
+    } else if end < 0 || start >= size as isize {
+        None
+    } else {
+        Some((start.max(0), end.min(size as isize - 1)))
+    }
+}
+
+// with less than 3 cells the cells on both sides would be the same one and pairs would be
+// visited twice, such axes don't wrap which skips collisions across the seam
+fn neighbour_axis(i: usize, delta: isize, size: usize, wrap: bool) -> Option<usize> {
+    let o: isize = i as isize + delta;
+    if o >= 0 && o < size as isize {
+        Some(o as usize)
+    } else if wrap && size >= 3 {
+        Some(o.rem_euclid(size as isize) as usize)
+    } else {
+        None
+    }
+}
+
+// objects bucketed by size into a uniform grid per level, cells of level k are 2^k times
+// the size of the finest level so that a few large objects don't make the cells of all
+// the small ones huge
+pub struct MultiLevelGrid {
+    pub levels: Vec<CellGrid>,
+    // periodic axes, set on every level when building
+    pub wrap_x: bool,
+    pub wrap_y: bool,
+    // largest radius each level is meant for
+    level_radii: Vec<f32>,
+    object_levels: Vec<usize>,
+    // positions and radii the grid was built from, cross level pairs are looked up with these
+    positions: Vec<Vec2>,
+    radii: Vec<f32>,
+}
+
+impl MultiLevelGrid {
+    pub fn new() -> Self {
+        Self {
+            levels: vec![],
+            wrap_x: false,
+            wrap_y: false,
+            level_radii: vec![],
+            object_levels: vec![],
+            positions: vec![],
+            radii: vec![],
+        }
+    }
+
+    // radius_factor is the cell size of a level relative to its largest radius
+    pub fn build(&mut self, positions: &[Vec2], radii: &[f32], radius_factor: f32, world_width: f32, world_height: f32) {
+        let min_radius: f32 = radii.iter().fold(f32::INFINITY, |min, &radius| min.min(radius)).max(1.0);
+
+        // smallest level that fits the object
+        self.level_radii.clear();
+        self.level_radii.push(min_radius);
+        self.object_levels.clear();
+        for &radius in radii {
+            let mut level: usize = 0;
+            while self.level_radii[level] < radius {
+                level += 1;
+                if level == self.level_radii.len() {
+                    self.level_radii.push(min_radius * 2f32.powi(level as i32));
+                }
+            }
+            self.object_levels.push(level);
+        }
+
+        self.levels.resize_with(self.level_radii.len(), CellGrid::new);
+        for (level, grid) in self.levels.iter_mut().enumerate() {
+            let object_levels: &[usize] = &self.object_levels;
+            grid.wrap_x = self.wrap_x;
+            grid.wrap_y = self.wrap_y;
+            grid.build_subset(positions, |i| object_levels[i] == level, self.level_radii[level] * radius_factor, world_width, world_height);
+        }
+
+        self.positions.clear();
+        self.positions.extend_from_slice(positions);
+        self.radii.clear();
+        self.radii.extend_from_slice(radii);
+    }
+
+    // pairs within a level use the half neighbourhood of that level, pairs across levels are
+    // found by the larger object searching every finer level, so each pair is visited once
+    pub fn for_each_pair(&self, mut solve: impl FnMut(usize, usize)) {
+        for grid in &self.levels {
+            grid.for_each_pair(&mut solve);
+        }
+        for (obj_index_1, &level) in self.object_levels.iter().enumerate() {
+            let position: Vec2 = self.positions[obj_index_1];
+            for (grid, &level_radius) in self.levels[..level].iter().zip(&self.level_radii) {
+                let reach: f32 = self.radii[obj_index_1] + level_radius;
+                let min: Vec2 = Vec2 {x: position.x - reach, y: position.y - reach};
+                let max: Vec2 = Vec2 {x: position.x + reach, y: position.y + reach};
+                grid.for_each_in_rect(min, max, |obj_index_2| solve(obj_index_1, obj_index_2));
+            }
+        }
+    }
+}
+
+// uniform grid over an unbounded world, cells are hashed into buckets which are stored
+// like the cells of the flat grid, so objects collide anywhere and not just on screen,
+// wrapped axes fold their cells into the world like the flat grid does
+pub struct SpatialHash {
+    pub cell_size: f32,
+    pub wrap_x: bool,
+    pub wrap_y: bool,
+    cell_width: f32,
+    cell_height: f32,
+    // number of cells along the wrapped axes, 0 for open ones
+    wrap_cells: (i32, i32),
+    bucket_start: Vec<usize>,
+    object_indices: Vec<usize>,
+    object_cells: Vec<(i32, i32)>,
+    object_buckets: Vec<usize>,
+}
+
+impl SpatialHash {
+    pub fn new() -> Self {
+        Self {
+            cell_size: 1.0,
+            wrap_x: false,
+            wrap_y: false,
+            cell_width: 1.0,
+            cell_height: 1.0,
+            wrap_cells: (0, 0),
+            bucket_start: vec![0],
+            object_indices: vec![],
+            object_cells: vec![],
+            object_buckets: vec![],
+        }
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        self.bucket_start.len() - 1
+    }
+
+    // the world size only matters along wrapped axes
+    pub fn build(&mut self, positions: &[Vec2], cell_size: f32, world_width: f32, world_height: f32) {
+        self.build_subset(positions, |_| true, cell_size, world_width, world_height);
+    }
+
+    // same as build but objects for which include returns false are left out of the hash
+    pub fn build_subset(&mut self, positions: &[Vec2], include: impl Fn(usize) -> bool, cell_size: f32, world_width: f32, world_height: f32) {
+        self.cell_size = cell_size;
+        let (width, cell_width): (usize, f32) = grid_axis(world_width, cell_size, self.wrap_x);
+        let (height, cell_height): (usize, f32) = grid_axis(world_height, cell_size, self.wrap_y);
+        (self.cell_width, self.cell_height) = (cell_width, cell_height);
+        self.wrap_cells = (
+            if self.wrap_x {width as i32} else {0},
+            if self.wrap_y {height as i32} else {0},
+        );
+        // twice as many buckets as objects keeps unrelated cells from sharing buckets too often
+        let bucket_count: usize = (positions.len() * 2).next_power_of_two();
+
+        // count objects per bucket
+        self.bucket_start.clear();
+        self.bucket_start.resize(bucket_count + 1, 0);
+        self.object_cells.clear();
+        self.object_buckets.clear();
+        for (i, position) in positions.iter().enumerate() {
+            let cell: (i32, i32) = (
+                fold_cell((position.x / cell_width).floor() as i32, self.wrap_cells.0),
+                fold_cell((position.y / cell_height).floor() as i32, self.wrap_cells.1),
+            );
+            let bucket: usize = if include(i) {
+                self.bucket_of(cell)
+            } else {
+                NO_CELL
+            };
+            if bucket != NO_CELL {
+                self.bucket_start[bucket + 1] += 1;
+            }
+            self.object_cells.push(cell);
+            self.object_buckets.push(bucket);
+        }
+
+        // prefix sum turns counts into start offsets
+        for bucket in 0..bucket_count {
+            self.bucket_start[bucket + 1] += self.bucket_start[bucket];
+        }
+
+        // scatter
+        let mut bucket_fill: Vec<usize> = self.bucket_start[..bucket_count].to_vec();
+        self.object_indices.clear();
+        self.object_indices.resize(self.bucket_start[bucket_count], 0);
+        for (i, &bucket) in self.object_buckets.iter().enumerate() {
+            if bucket != NO_CELL {
+                self.object_indices[bucket_fill[bucket]] = i;
+                bucket_fill[bucket] += 1;
+            }
+        }
+    }
+
+    fn bucket_of(&self, (x, y): (i32, i32)) -> usize {
+        let hash: u32 = (x as u32).wrapping_mul(73_856_093) ^ (y as u32).wrapping_mul(19_349_663);
+        hash as usize & (self.bucket_count() - 1)
+    }
+
+    // every object looks through its 9 neighbouring cells and only takes objects with a higher
+    // index, checking the cell as well skips objects whose cells merely share a bucket
+    pub fn for_each_pair(&self, mut solve: impl FnMut(usize, usize)) {
+        for (obj_index_1, &(x, y)) in self.object_cells.iter().enumerate() {
+            if self.object_buckets[obj_index_1] == NO_CELL {
+                continue;
+            }
+            let (rows, row_count): ([i32; 3], usize) = neighbour_cells(y, self.wrap_cells.1);
+            let (columns, column_count): ([i32; 3], usize) = neighbour_cells(x, self.wrap_cells.0);
+            for &neighbour_y in &rows[..row_count] {
+                for &neighbour_x in &columns[..column_count] {
+                    let cell: (i32, i32) = (neighbour_x, neighbour_y);
+                    let bucket: usize = self.bucket_of(cell);
+                    for &obj_index_2 in &self.object_indices[self.bucket_start[bucket]..self.bucket_start[bucket + 1]] {
+                        if obj_index_2 > obj_index_1 && self.object_cells[obj_index_2] == cell {
+                            solve(obj_index_1, obj_index_2);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const WORLD_WIDTH: f32 = 200.0;
+    const MAX_RADIUS: f32 = 5.0;
+
+    // random positions and radii, dense enough that most objects overlap some others
+    fn scene(count: usize, world_height: f32) -> (Vec<Vec2>, Vec<f32>) {
+        let mut rng: StdRng = StdRng::seed_from_u64(7);
+        let positions: Vec<Vec2> = (0..count)
+            .map(|_| Vec2 {x: rng.gen_range(0.0..WORLD_WIDTH), y: rng.gen_range(0.0..world_height)})
+            .collect();
+        let radii: Vec<f32> = (0..count).map(|_| rng.gen_range(1.0..MAX_RADIUS)).collect();
+        (positions, radii)
+    }
+
+    // shortest difference along an axis, through the seam on wrapped axes
+    fn axis_distance(a: f32, b: f32, world_size: f32, wrap: bool) -> f32 {
+        let distance: f32 = (a - b).abs();
+        if wrap {
+            distance.min(world_size - distance)
+        } else {
+            distance
+        }
+    }
+
+    // periodic axes and height of the world a scene is checked in
+    #[derive(Clone, Copy)]
+    struct World {
+        wrap_x: bool,
+        wrap_y: bool,
+        height: f32,
+    }
+
+    impl World {
+        fn overlapping(&self, positions: &[Vec2], radii: &[f32], i: usize, j: usize) -> bool {
+            let dx: f32 = axis_distance(positions[i].x, positions[j].x, WORLD_WIDTH, self.wrap_x);
+            let dy: f32 = axis_distance(positions[i].y, positions[j].y, self.height, self.wrap_y);
+            dx * dx + dy * dy < (radii[i] + radii[j]) * (radii[i] + radii[j])
+        }
+    }
+
+    // a broadphase has to find exactly the overlapping pairs brute force finds, each of them once
+    // and no object with itself, returns the number of pairs it checked
+    fn check_overlaps(world: World, positions: &[Vec2], radii: &[f32], for_each_pair: impl FnOnce(&mut dyn FnMut(usize, usize))) -> usize {
+        let mut visited: HashSet<(usize, usize)> = HashSet::new();
+        let mut overlaps: HashSet<(usize, usize)> = HashSet::new();
+        let mut checks: usize = 0;
+        for_each_pair(&mut |i, j| {
+            assert_ne!(i, j, "object paired with itself");
+            assert!(visited.insert((i.min(j), i.max(j))), "pair ({i}, {j}) visited twice");
+            if world.overlapping(positions, radii, i, j) {
+                overlaps.insert((i.min(j), i.max(j)));
+            }
+            checks += 1;
+        });
+
+        let mut brute_force: HashSet<(usize, usize)> = HashSet::new();
+        for i in 0..positions.len() {
+            for j in i + 1..positions.len() {
+                if world.overlapping(positions, radii, i, j) {
+                    brute_force.insert((i, j));
+                }
+            }
+        }
+        assert!(!brute_force.is_empty());
+        assert_eq!(overlaps, brute_force);
+        checks
+    }
+
+    // the half neighbourhood also takes half the checks of the full neighbourhood
+    fn check_pairs(wrap_x: bool, wrap_y: bool, world_height: f32) {
+        let (positions, radii): (Vec<Vec2>, Vec<f32>) = scene(400, world_height);
+        let mut grid: CellGrid = CellGrid::new();
+        grid.wrap_x = wrap_x;
+        grid.wrap_y = wrap_y;
+        grid.build(&positions, MAX_RADIUS * 2.0, WORLD_WIDTH, world_height);
+
+        let world: World = World {wrap_x, wrap_y, height: world_height};
+        let checks: usize = check_overlaps(world, &positions, &radii, |solve| grid.for_each_pair(solve));
+        let mut full_checks: usize = 0;
+        grid.for_each_pair_full(|_, _| full_checks += 1);
+        assert_eq!(full_checks, checks * 2);
+    }
+
+    // levels with the tightest cells that still hold every overlapping pair
+    fn check_multi_level(wrap_x: bool, wrap_y: bool, world_height: f32) {
+        let (positions, radii): (Vec<Vec2>, Vec<f32>) = scene(400, world_height);
+        let mut grid: MultiLevelGrid = MultiLevelGrid::new();
+        grid.wrap_x = wrap_x;
+        grid.wrap_y = wrap_y;
+        grid.build(&positions, &radii, 2.0, WORLD_WIDTH, world_height);
+        assert!(grid.levels.len() > 1);
+
+        let world: World = World {wrap_x, wrap_y, height: world_height};
+        check_overlaps(world, &positions, &radii, |solve| grid.for_each_pair(solve));
+    }
+
+    fn check_spatial_hash(wrap_x: bool, wrap_y: bool, world_height: f32) {
+        let (positions, radii): (Vec<Vec2>, Vec<f32>) = scene(400, world_height);
+        let mut hash: SpatialHash = SpatialHash::new();
+        hash.wrap_x = wrap_x;
+        hash.wrap_y = wrap_y;
+        hash.build(&positions, MAX_RADIUS * 2.0, WORLD_WIDTH, world_height);
+
+        let world: World = World {wrap_x, wrap_y, height: world_height};
+        check_overlaps(world, &positions, &radii, |solve| hash.for_each_pair(solve));
+    }
+
+    #[test]
+    fn half_neighbourhood_finds_every_pair_once() {
+        check_pairs(false, false, 150.0);
+    }
+
+    #[test]
+    fn half_neighbourhood_across_seams() {
+        check_pairs(true, true, 150.0);
+    }
+
+    // 2 and 1 cells high, too few for the wrapped neighbours to differ from the direct ones
+    #[test]
+    fn half_neighbourhood_with_few_wrapped_cells() {
+        check_pairs(true, true, 25.0);
+        check_pairs(false, true, 12.0);
+    }
+
+    #[test]
+    fn multi_level_finds_every_pair_once() {
+        check_multi_level(false, false, 150.0);
+    }
+
+    // the coarse levels of the 25 high world have only a cell or two along the wrapped axis
+    #[test]
+    fn multi_level_across_seams() {
+        check_multi_level(true, true, 150.0);
+        check_multi_level(true, false, 150.0);
+        check_multi_level(false, true, 25.0);
+    }
+
+    #[test]
+    fn spatial_hash_across_seams() {
+        check_spatial_hash(false, false, 150.0);
+        check_spatial_hash(true, true, 150.0);
+        check_spatial_hash(true, true, 25.0);
+        check_spatial_hash(false, true, 12.0);
+    }
+
+    // far outside any window, with every third object left out of the hash
+    #[test]
+    fn spatial_hash_subset_finds_pairs_anywhere() {
+        let (mut positions, radii): (Vec<Vec2>, Vec<f32>) = scene(400, 150.0);
+        for position in &mut positions {
+            *position -= Vec2 {x: 5_000.0, y: 3_000.0};
+        }
+        let included = |i: usize| !i.is_multiple_of(3);
+        let mut hash: SpatialHash = SpatialHash::new();
+        hash.build_subset(&positions, included, MAX_RADIUS * 2.0, WORLD_WIDTH, 150.0);
+
+        let world: World = World {wrap_x: false, wrap_y: false, height: 150.0};
+        let mut overlaps: HashSet<(usize, usize)> = HashSet::new();
+        hash.for_each_pair(|i, j| {
+            assert!(included(i) && included(j), "pair ({i}, {j}) includes a left out object");
+            assert!(i < j);
+            if world.overlapping(&positions, &radii, i, j) {
+                assert!(overlaps.insert((i, j)), "pair ({i}, {j}) visited twice");
+            }
+        });
+
+        let mut brute_force: HashSet<(usize, usize)> = HashSet::new();
+        for i in (0..positions.len()).filter(|&i| included(i)) {
+            for j in (i + 1..positions.len()).filter(|&j| included(j)) {
+                if world.overlapping(&positions, &radii, i, j) {
+                    brute_force.insert((i, j));
+                }
+            }
+        }
+        assert!(!brute_force.is_empty());
+        assert_eq!(overlaps, brute_force);
+    }
+}
@@ -8,11 +8,27 @@ use ui::Windows;
 mod vector;
 mod verlet;
 mod solver;
+mod grid;
+mod benchmark;
 mod ui;
 mod context;
 mod render;
 mod syntax_highlighting;
 mod shaders;
+mod batch;
+mod color_map;
+mod container;
+mod timeline;
+mod gravity;
+mod nbody;
+mod forces;
+mod material;
+mod fluid;
+mod softbody;
+mod rigidbody;
+mod shape;
+mod thermal;
+mod phase;
 
 #[macroquad::main("mq-verlet")]
 async fn main() {
@@ -22,6 +38,8 @@ async fn main() {
     let mut rng: ThreadRng = thread_rng();
 
     loop {
+        context.solver.set_world_size(Vec2 {x: screen_width(), y: screen_height()});
+
         // logic
         let now: f64 = get_time();
         let mut frame_time: f64 = now - last_frame;
@@ -33,6 +51,7 @@ async fn main() {
                 frame_time = frame_time.max(1.0 / context.sfps_max);
             }
             context.solver.update_with_substep(frame_time as f32, context.sim_substeps);
+            context.automate(frame_time as f32);
             context.last_sim_frame_time = frame_time as f32;
             last_frame = now;
         }
@@ -0,0 +1,133 @@
+use crate::phase::Phase;
+
+pub const MATERIAL_COUNT: usize = 4;
+
+// thermal properties shared by the default materials, the transition temperatures are only used
+// if phase changes get turned on for them
+const NO_PHASE_CHANGES: Material = Material {
+    name: "",
+    wall_adhesion: 0.0,
+    fluid: false,
+    specific_heat: 1.0,
+    changes_phase: false,
+    melting_point: -0.5,
+    boiling_point: 1.0,
+    latent_heat: 0.0,
+    thermal_expansion: 0.0,
+};
+
+#[derive(Clone, Copy)]
+pub struct Material {
+    pub name: &'static str,
+    // pull toward walls just out of contact, as an acceleration
+    pub wall_adhesion: f32,
+    // solved as a liquid when the fluid mode is enabled
+    pub fluid: bool,
+    // heat capacity per unit of mass
+    pub specific_heat: f32,
+    // freezes, melts, boils and condenses at the temperatures below when phase changes are enabled
+    pub changes_phase: bool,
+    pub melting_point: f32,
+    pub boiling_point: f32,
+    // heat per unit of mass taken in on melting or boiling
+    pub latent_heat: f32,
+    // growth of the radius per degree, relative to the rest radius
+    pub thermal_expansion: f32,
+}
+
+impl Material {
+    pub fn phase_at(&self, temperature: f32) -> Phase {
+        if !self.changes_phase {
+            Phase::Liquid
+        } else if temperature < self.melting_point {
+            Phase::Solid
+        } else if temperature > self.boiling_point {
+            Phase::Gas
+        } else {
+            Phase::Liquid
+        }
+    }
+
+    // temperature of the change into the next hotter phase
+    pub fn hotter_transition(&self, phase: Phase) -> Option<f32> {
+        match phase {
+            Phase::Solid => Some(self.melting_point),
+            Phase::Liquid => Some(self.boiling_point),
+            Phase::Gas => None,
+        }
+    }
+
+    // temperature of the change into the next colder phase
+    pub fn colder_transition(&self, phase: Phase) -> Option<f32> {
+        match phase {
+            Phase::Solid => None,
+            Phase::Liquid => Some(self.melting_point),
+            Phase::Gas => Some(self.boiling_point),
+        }
+    }
+}
+
+// per-object material index into the list, with the interactions between every pair of materials
+#[derive(Clone, Copy)]
+pub struct Materials {
+    pub list: [Material; MATERIAL_COUNT],
+    // pull between objects of two materials just out of contact, as an acceleration, symmetric
+    pub cohesion: [[f32; MATERIAL_COUNT]; MATERIAL_COUNT],
+    pub cohesion_enabled: bool,
+    pub adhesion_enabled: bool,
+    // width of the attraction band beyond contact, as a fraction of the contact distance
+    pub band: f32,
+}
+
+impl Materials {
+    pub fn default() -> Self {
+        Self {
+            list: [
+                Material {name: "Default", wall_adhesion: 0.0, fluid: false, specific_heat: 1.0, ..NO_PHASE_CHANGES},
+                Material {
+                    name: "Water",
+                    wall_adhesion: 2_000.0,
+                    fluid: true,
+                    specific_heat: 4.0,
+                    changes_phase: true,
+                    melting_point: -0.5,
+                    boiling_point: 1.0,
+                    latent_heat: 5.0,
+                    // steam takes more room than ice, the frozen cube stays within bond reach at -1
+                    thermal_expansion: 0.05,
+                },
+                Material {name: "Sand", wall_adhesion: 0.0, fluid: false, specific_heat: 0.8, ..NO_PHASE_CHANGES},
+                Material {name: "Goo", wall_adhesion: 8_000.0, fluid: false, specific_heat: 2.0, ..NO_PHASE_CHANGES},
+            ],
+            cohesion: [
+                [0.0, 0.0, 0.0, 0.0],
+                [0.0, 3_000.0, 500.0, 1_000.0],
+                [0.0, 500.0, 0.0, 500.0],
+                [0.0, 1_000.0, 500.0, 10_000.0],
+            ],
+            cohesion_enabled: false,
+            adhesion_enabled: false,
+            band: 0.25,
+        }
+    }
+
+    pub fn name(&self, material: u8) -> &'static str {
+        self.list[material as usize].name
+    }
+
+    pub fn set_cohesion(&mut self, material_1: usize, material_2: usize, strength: f32) {
+        self.cohesion[material_1][material_2] = strength;
+        self.cohesion[material_2][material_1] = strength;
+    }
+
+    // the attraction rises from zero at contact to the full strength halfway through the band
+    // and falls back to zero at its end, so objects entering or leaving it don't get kicked
+    pub fn band_profile(&self, gap: f32, contact_distance: f32) -> f32 {
+        let width: f32 = contact_distance * self.band;
+        if gap <= 0.0 || gap >= width {
+            return 0.0;
+        }
+        let t: f32 = gap / width;
+        4.0 * t * (1.0 - t)
+    }
+}
@@ -0,0 +1,298 @@
+use crate::{vector::Vec2, verlet::mass};
+
+// leaves stop splitting at this depth so coincident objects don't recurse forever
+const MAX_DEPTH: usize = 16;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum NBodyMethod {
+    // quadtree approximating far away groups of objects by their center of mass
+    BarnesHut,
+    // every pair of objects, exact but quadratic, kept as a reference
+    Direct,
+}
+
+impl NBodyMethod {
+    pub const ALL: [Self; 2] = [
+        Self::BarnesHut,
+        Self::Direct,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BarnesHut => "Barnes-Hut",
+            Self::Direct => "Direct (reference)",
+        }
+    }
+}
+
+// mutual attraction of objects, with the mass proportional to the area of the object
+pub struct NBody {
+    pub enabled: bool,
+    pub method: NBodyMethod,
+    pub gravitational_constant: f32,
+    // added to the distance so close objects don't get infinite accelerations
+    pub softening: f32,
+    // opening angle, groups smaller than this fraction of their distance are approximated
+    pub theta: f32,
+    pub quad_tree: QuadTree,
+}
+
+impl NBody {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            method: NBodyMethod::BarnesHut,
+            gravitational_constant: 100.0,
+            softening: 5.0,
+            theta: 0.5,
+            quad_tree: QuadTree::new(),
+        }
+    }
+
+    // accelerations of all objects, without the gravitational constant
+    pub fn accelerations(&mut self, positions: &[Vec2], radii: &[f32]) -> Vec<Vec2> {
+        let masses: Vec<f32> = radii.iter().map(|&radius| mass(radius)).collect();
+        match self.method {
+            NBodyMethod::BarnesHut => {
+                self.quad_tree.build(positions, &masses);
+                positions.iter().map(|&position| self.quad_tree.acceleration(position, self.theta, self.softening)).collect()
+            },
+            NBodyMethod::Direct => direct_accelerations(positions, &masses, self.softening),
+        }
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    pub fn par_accelerations(&mut self, positions: &[Vec2], radii: &[f32]) -> Vec<Vec2> {
+        use rayon::prelude::*;
+        let masses: Vec<f32> = radii.par_iter().map(|&radius| mass(radius)).collect();
+        let softening: f32 = self.softening;
+        match self.method {
+            NBodyMethod::BarnesHut => {
+                self.quad_tree.build(positions, &masses);
+                let (quad_tree, theta) = (&self.quad_tree, self.theta);
+                positions.par_iter().map(|&position| quad_tree.acceleration(position, theta, softening)).collect()
+            },
+            NBodyMethod::Direct => positions.par_iter()
+                .map(|&position| direct_acceleration(position, positions, &masses, softening))
+                .collect(),
+        }
+    }
+}
+
+// softened inverse square pull of a mass, the object itself is at zero offset and adds nothing
+fn attraction(position: Vec2, mass_position: Vec2, mass: f32, softening: f32) -> Vec2 {
+    let offset: Vec2 = mass_position - position;
+    let distance_squared: f32 = offset.x * offset.x + offset.y * offset.y + softening * softening;
+    if distance_squared <= f32::EPSILON {
+        return Vec2::zero();
+    }
+    offset * (mass / (distance_squared * distance_squared.sqrt()))
+}
+
+pub fn direct_acceleration(position: Vec2, positions: &[Vec2], masses: &[f32], softening: f32) -> Vec2 {
+    let mut acceleration: Vec2 = Vec2::zero();
+    for (&other, &mass) in positions.iter().zip(masses.iter()) {
+        acceleration += attraction(position, other, mass, softening);
+    }
+    acceleration
+}
+
+pub fn direct_accelerations(positions: &[Vec2], masses: &[f32], softening: f32) -> Vec<Vec2> {
+    positions.iter().map(|&position| direct_acceleration(position, positions, masses, softening)).collect()
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    center: Vec2,
+    half_size: f32,
+    mass: f32,
+    mass_center: Vec2,
+    // index of the first of four consecutive children, zero for leaves (the root is never a child)
+    children: usize,
+    // range of the objects inside the node in the sorted object indices
+    start: usize,
+    end: usize,
+}
+
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    // object indices, sorted so that every node covers a contiguous range
+    indices: Vec<usize>,
+    positions: Vec<Vec2>,
+    masses: Vec<f32>,
+}
+
+impl QuadTree {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            indices: vec![],
+            positions: vec![],
+            masses: vec![],
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn build(&mut self, positions: &[Vec2], masses: &[f32]) {
+        self.nodes.clear();
+        self.indices.clear();
+        self.indices.extend(0..positions.len());
+        self.positions.clear();
+        self.positions.extend_from_slice(positions);
+        self.masses.clear();
+        self.masses.extend_from_slice(masses);
+        if positions.is_empty() {
+            return;
+        }
+
+        // square bounds around every object
+        let mut min: Vec2 = positions[0];
+        let mut max: Vec2 = positions[0];
+        for position in positions {
+            min.x = min.x.min(position.x);
+            min.y = min.y.min(position.y);
+            max.x = max.x.max(position.x);
+            max.y = max.y.max(position.y);
+        }
+        let center: Vec2 = (min + max) * 0.5;
+        let half_size: f32 = ((max.x - min.x).max(max.y - min.y) * 0.5).max(1.0);
+        self.nodes.push(Node {
+            center,
+            half_size,
+            mass: 0.0,
+            mass_center: Vec2::zero(),
+            children: 0,
+            start: 0,
+            end: positions.len(),
+        });
+        self.split(0, 0);
+    }
+
+    fn quadrant(&self, center: Vec2, index: usize) -> usize {
+        let position: Vec2 = self.positions[index];
+        (position.x >= center.x) as usize + 2 * (position.y >= center.y) as usize
+    }
+
+    // fills in the mass of the node, splitting it into four children when it holds more than one object
+    fn split(&mut self, node: usize, depth: usize) {
+        let Node {center, half_size, start, end, ..} = self.nodes[node];
+        let mut mass: f32 = 0.0;
+        let mut weighted: Vec2 = Vec2::zero();
+        for &index in &self.indices[start..end] {
+            mass += self.masses[index];
+            weighted += self.positions[index] * self.masses[index];
+        }
+        self.nodes[node].mass = mass;
+        self.nodes[node].mass_center = if mass > 0.0 {weighted * (1.0 / mass)} else {center};
+        if end - start <= 1 || depth >= MAX_DEPTH {
+            return;
+        }
+
+        let mut indices: Vec<usize> = std::mem::take(&mut self.indices);
+        indices[start..end].sort_unstable_by_key(|&index| self.quadrant(center, index));
+        let mut bounds: [usize; 5] = [start, start, start, start, end];
+        for (quadrant, bound) in bounds.iter_mut().enumerate().take(4).skip(1) {
+            *bound = start + indices[start..end].partition_point(|&index| self.quadrant(center, index) < quadrant);
+        }
+        self.indices = indices;
+
+        let children: usize = self.nodes.len();
+        self.nodes[node].children = children;
+        let quarter: f32 = half_size * 0.5;
+        for quadrant in 0..4 {
+            let offset: Vec2 = Vec2 {
+                x: if quadrant & 1 == 1 {quarter} else {-quarter},
+                y: if quadrant & 2 == 2 {quarter} else {-quarter},
+            };
+            self.nodes.push(Node {
+                center: center + offset,
+                half_size: quarter,
+                mass: 0.0,
+                mass_center: Vec2::zero(),
+                children: 0,
+                start: bounds[quadrant],
+                end: bounds[quadrant + 1],
+            });
+        }
+        for quadrant in 0..4 {
+            self.split(children + quadrant, depth + 1);
+        }
+    }
+
+    // acceleration at a position, without the gravitational constant
+    pub fn acceleration(&self, position: Vec2, theta: f32, softening: f32) -> Vec2 {
+        let mut acceleration: Vec2 = Vec2::zero();
+        if self.nodes.is_empty() {
+            return acceleration;
+        }
+        let mut stack: Vec<usize> = vec![0];
+        while let Some(index) = stack.pop() {
+            let node: &Node = &self.nodes[index];
+            if node.mass <= 0.0 {
+                continue;
+            }
+            if node.children == 0 {
+                for &object in &self.indices[node.start..node.end] {
+                    acceleration += attraction(position, self.positions[object], self.masses[object], softening);
+                }
+                continue;
+            }
+            // a node around the position itself is always opened, however far its center of mass is
+            let inside: bool = (position.x - node.center.x).abs() <= node.half_size && (position.y - node.center.y).abs() <= node.half_size;
+            let distance: f32 = (node.mass_center - position).len();
+            if !inside && node.half_size * 2.0 < theta * distance {
+                acceleration += attraction(position, node.mass_center, node.mass, softening);
+            } else {
+                stack.extend(node.children..node.children + 4);
+            }
+        }
+        acceleration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const SOFTENING: f32 = 5.0;
+
+    // scattered objects of different masses plus a stack of coincident ones, which can't be
+    // split apart and end up together in a leaf at the maximum depth
+    fn scene() -> (Vec<Vec2>, Vec<f32>) {
+        let mut rng: StdRng = StdRng::seed_from_u64(3);
+        let mut positions: Vec<Vec2> = (0..500)
+            .map(|_| Vec2 {x: rng.gen_range(0.0..1000.0), y: rng.gen_range(0.0..600.0)})
+            .collect();
+        positions.extend([Vec2 {x: 250.0, y: 150.0}; 20]);
+        let masses: Vec<f32> = positions.iter().map(|_| mass(rng.gen_range(2.0..10.0))).collect();
+        (positions, masses)
+    }
+
+    // largest difference to the direct sum, relative to the mean direct acceleration
+    fn max_error(theta: f32) -> f32 {
+        let (positions, masses): (Vec<Vec2>, Vec<f32>) = scene();
+        let mut quad_tree: QuadTree = QuadTree::new();
+        quad_tree.build(&positions, &masses);
+        let reference: Vec<Vec2> = direct_accelerations(&positions, &masses, SOFTENING);
+        let mean: f32 = reference.iter().map(|acceleration| acceleration.len()).sum::<f32>() / reference.len() as f32;
+        positions.iter().zip(reference.iter())
+            .map(|(&position, &expected)| (quad_tree.acceleration(position, theta, SOFTENING) - expected).len() / mean)
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn barnes_hut_without_opening_angle_is_exact() {
+        let error: f32 = max_error(0.0);
+        assert!(error < 1e-4, "error {error}");
+    }
+
+    #[test]
+    fn barnes_hut_error_is_bounded() {
+        let error: f32 = max_error(0.5);
+        assert!(error < 0.05, "error {error}");
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{material::Material, verlet};
+
+// solid objects closer than this factor of their contact distance count as touching when bonding,
+// frozen objects rarely sit exactly at contact distance
+pub const BOND_REACH: f32 = 1.1;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase {
+    Solid,
+    // objects of materials without phase changes stay liquid, which changes nothing about them
+    Liquid,
+    Gas,
+}
+
+impl Phase {
+    pub const ALL: [Self; 3] = [
+        Self::Solid,
+        Self::Liquid,
+        Self::Gas,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Solid => "Solid",
+            Self::Liquid => "Liquid",
+            Self::Gas => "Gas",
+        }
+    }
+
+    fn hotter(&self) -> Self {
+        match self {
+            Self::Solid => Self::Liquid,
+            Self::Liquid | Self::Gas => Self::Gas,
+        }
+    }
+
+    fn colder(&self) -> Self {
+        match self {
+            Self::Solid | Self::Liquid => Self::Solid,
+            Self::Gas => Self::Liquid,
+        }
+    }
+}
+
+// moves an object between phases as its temperature crosses the transition temperatures of its material,
+// with latent heat the temperature stays at the transition until the heat of the change has been taken
+// in or given off, phase_heat keeps the part that has, positive toward the hotter phase and negative
+// toward the colder one, latent_heat is the heat of the whole object, zero changes phase right away
+pub fn transition(material: &Material, latent_heat: f32, heat_capacity: f32, phase: &mut Phase, temperature: &mut f32, phase_heat: &mut f32) {
+    let heat_capacity: f32 = heat_capacity.max(f32::EPSILON);
+    // a large step can go from solid straight to gas
+    for _ in 0..2 {
+        if let Some(point) = material.hotter_transition(*phase).filter(|&point| *temperature > point || *phase_heat > 0.0) {
+            *phase_heat += (*temperature - point) * heat_capacity;
+            *temperature = point;
+            if *phase_heat >= latent_heat {
+                *phase = phase.hotter();
+                *temperature = point + (*phase_heat - latent_heat) / heat_capacity;
+                *phase_heat = 0.0;
+                continue;
+            }
+            if *phase_heat < 0.0 {
+                *temperature = point + *phase_heat / heat_capacity;
+                *phase_heat = 0.0;
+            }
+        } else if let Some(point) = material.colder_transition(*phase).filter(|&point| *temperature < point || *phase_heat < 0.0) {
+            *phase_heat += (*temperature - point) * heat_capacity;
+            *temperature = point;
+            if *phase_heat <= -latent_heat {
+                *phase = phase.colder();
+                *temperature = point + (*phase_heat + latent_heat) / heat_capacity;
+                *phase_heat = 0.0;
+                continue;
+            }
+            if *phase_heat > 0.0 {
+                *temperature = point + *phase_heat / heat_capacity;
+                *phase_heat = 0.0;
+            }
+        }
+        break;
+    }
+}
+
+// spring holding two frozen objects together
+#[derive(Clone, Copy)]
+pub struct Bond {
+    pub i: usize,
+    pub j: usize,
+    pub rest_length: f32,
+}
+
+pub struct PhaseChanges {
+    pub enabled: bool,
+    // heat taken in on melting and boiling and given off on freezing and condensing
+    pub latent_heat_enabled: bool,
+    // between touching solid objects of the same material, formed when one of them freezes
+    // and broken when either melts
+    pub bonds: Vec<Bond>,
+    // fraction of the length error of a bond corrected every substep
+    pub bond_stiffness: f32,
+    // fraction of gravity gas objects feel, negative rises like a light gas in heavier air
+    pub gas_gravity: f32,
+}
+
+impl PhaseChanges {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            latent_heat_enabled: true,
+            bonds: vec![],
+            bond_stiffness: 0.5,
+            gas_gravity: -0.3,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bonds.clear();
+    }
+
+    // latent heat of a whole object of the material and rest radius
+    pub fn latent_heat(&self, material: &Material, rest_radius: f32) -> f32 {
+        if self.latent_heat_enabled {
+            material.latent_heat * verlet::mass(rest_radius)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn break_bonds_of(&mut self, index: usize) {
+        self.bonds.retain(|bond| bond.i != index && bond.j != index);
+    }
+
+    // drops the bonds of the object at index and keeps the rest pointing at the same objects
+    pub fn remove_object(&mut self, index: usize) {
+        self.break_bonds_of(index);
+        for bond in self.bonds.iter_mut() {
+            if bond.i > index {
+                bond.i -= 1;
+            }
+            if bond.j > index {
+                bond.j -= 1;
+            }
+        }
+    }
+
+    // object order[i] became object i
+    pub fn permute(&mut self, order: &[usize]) {
+        let new_indices: Vec<usize> = verlet::inverse_order(order);
+        for bond in self.bonds.iter_mut() {
+            bond.i = new_indices[bond.i];
+            bond.j = new_indices[bond.j];
+        }
+    }
+}
@@ -1,63 +1,112 @@
 
 use macroquad::prelude::*;
+use crate::batch::CircleInstance;
 use crate::context::Context;
 use crate::vector::Vec2;
 
 pub fn render(context: &mut Context) {
     // basics
     clear_background(BLACK);
-    let mut oob_text: Vec<String> = vec![];
+    // objects outside of the window are normal in unbounded worlds, so they are only counted
+    let mut oob_count: usize = 0;
+    context.solver.container.draw();
+    for zone in &context.solver.gravity_zones {
+        zone.draw();
+    }
+    for well in &context.solver.gravity_wells {
+        well.draw();
+    }
+    for body in &context.solver.soft_bodies.bodies {
+        body.draw(&context.solver.linked_positions(&body.indices));
+    }
+    for bond in &context.solver.phase_changes.bonds {
+        let ends: Vec<Vec2> = context.solver.linked_positions(&[bond.i, bond.j]);
+        draw_line(ends[0].x, ends[0].y, ends[1].x, ends[1].y, 1.0, Color::new(0.8, 0.9, 1.0, 0.5));
+    }
 
-    // shaders
-    if context.shader_context.use_shaders {
-        set_camera(&Camera2D {
-            zoom: vec2(2.0/screen_width(), -2.0/screen_height()),
-            target: vec2(screen_width()/2.0, screen_height()/2.0),
-            ..Default::default()
-        });
-        gl_use_material(context.shader_context.material);
+    // cpu color mapping
+    if context.color_map.is_enabled() {
+        context.color_map.update_range(&context.solver.verlet_objects, context.solver.last_dt);
     }
 
-    // object rendering
-    for verlet_object in &context.solver.verlet_objects {
+    // object rendering (shaders are only supported by the batched renderer, which needs instancing)
+    let batched: bool = context.circle_batch.instancing
+        && (context.batched_rendering || context.shader_context.use_shaders);
+    context.circle_batch.clear();
+    for verlet_object in context.solver.verlet_objects.iter() {
         let Vec2{x, y} = verlet_object.position_current;
         let r = verlet_object.radius;
         if !(-r..screen_width()+r).contains(&x) || !(-r..screen_height()+r).contains(&y) {
-            oob_text.push(format!("OOB: [{}, {}]", x, y));
+            oob_count += 1;
         } else {
-
-            if context.shader_context.use_shaders {
-                if context.shader_context.use_uniform_pos_old {
-                    context.shader_context.material.set_uniform("pos_old", verlet_object.position_old.as_tuple());
-                }
-                if context.shader_context.use_uniform_pos_curr {
-                    context.shader_context.material.set_uniform("pos_curr", verlet_object.position_current.as_tuple());
-                }
-                if context.shader_context.use_uniform_acceleration {
-                    context.shader_context.material.set_uniform("acceleration", verlet_object.acceleration.as_tuple());
-                }
-                if context.shader_context.use_uniform_radius {
-                    context.shader_context.material.set_uniform("radius", verlet_object.radius);
-                }
-                if context.shader_context.use_uniform_temperature {
-                    context.shader_context.material.set_uniform("temperature", verlet_object.temperature);
+            let color: Color = if context.color_map.is_enabled() {
+                context.color_map.color(&verlet_object, context.solver.last_dt)
+            } else {
+                verlet_object.color
+            };
+            // objects overlapping the edge of a wrapped axis get ghost copies on the opposite side
+            let ghost_x: Option<f32> = ghost_offset(x, r, screen_width(), context.solver.wrap_x);
+            let ghost_y: Option<f32> = ghost_offset(y, r, screen_height(), context.solver.wrap_y);
+            let offsets: [Option<Vec2>; 4] = [
+                Some(Vec2::zero()),
+                ghost_x.map(|dx| Vec2{x: dx, y: 0.0}),
+                ghost_y.map(|dy| Vec2{x: 0.0, y: dy}),
+                ghost_x.zip(ghost_y).map(|(dx, dy)| Vec2{x: dx, y: dy}),
+            ];
+            for offset in offsets.into_iter().flatten() {
+                let mut copy = verlet_object;
+                copy.position_current += offset;
+                copy.position_old += offset;
+                // other shapes are drawn right away, without the batch and its shaders
+                if !copy.shape.is_circle() {
+                    copy.shape.draw(copy.position_current, copy.angle, r, color, &context.solver.polygons);
+                } else if batched {
+                    context.circle_batch.push(CircleInstance::new(&copy, color));
+                } else {
+                    draw_circle(x + offset.x, y + offset.y, r, color);
                 }
             }
+        }
+    }
+    if batched {
+        let pipeline = if context.shader_context.use_shaders {
+            context.shader_context.pipeline
+        } else {
+            context.circle_batch.default_pipeline
+        };
+        context.circle_batch.draw(pipeline);
+    }
 
-            draw_circle(x, y, r, Color::new(1.0, 1.0, 1.0, 0.5));
+    if context.show_rotation_markers {
+        let objects = &context.solver.verlet_objects;
+        for i in 0..objects.len() {
+            if objects.shape[i].is_circle() {
+                let Vec2{x, y} = objects.position_current[i];
+                let rim: Vec2 = objects.position_current[i] + Vec2{x: objects.radius[i], y: 0.0}.rotated(objects.angle[i]);
+                draw_line(x, y, rim.x, rim.y, (objects.radius[i] * 0.15).max(1.0), Color::new(0.0, 0.0, 0.0, 0.6));
+            }
         }
     }
 
-    // shader clean up
-    if context.shader_context.use_shaders {
-        set_default_camera();
-        gl_use_default_material();
+    // color map legend
+    if context.color_map.is_enabled() && context.color_map.show_legend {
+        context.color_map.draw_legend();
     }
 
-    // oob object rendering (text)
-    if !context.shader_context.use_shaders {
-        for (i, text) in oob_text.iter().enumerate() {
-            draw_text(text, 0.0, 20.0 * i as f32, 20., RED);
-        }
+    // oob object count (text)
+    if !context.shader_context.use_shaders && oob_count > 0 {
+        draw_text(&format!("OOB: {}", oob_count), 0.0, 20.0, 20., RED);
+    }
+}
+
+fn ghost_offset(position: f32, radius: f32, size: f32, wrap: bool) -> Option<f32> {
+    if !wrap {
+        None
+    } else if position < radius {
+        Some(size)
+    } else if position > size - radius {
+        Some(-size)
+    } else {
+        None
     }
 }
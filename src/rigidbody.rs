@@ -0,0 +1,167 @@
+use crate::{vector::Vec2, verlet};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RigidShape {
+    Box,
+    Rod,
+    LShape,
+    Gear,
+}
+
+impl RigidShape {
+    pub const ALL: [Self; 4] = [
+        Self::Box,
+        Self::Rod,
+        Self::LShape,
+        Self::Gear,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Box => "Box",
+            Self::Rod => "Rod",
+            Self::LShape => "L-shape",
+            Self::Gear => "Gear",
+        }
+    }
+
+    // member positions around the origin for a shape size objects across, touching each other
+    pub fn offsets(&self, size: usize, spacing: f32) -> Vec<Vec2> {
+        let size: usize = size.max(2);
+        let grid = |x: usize, y: usize| Vec2 {x: x as f32 * spacing, y: y as f32 * spacing};
+        let mut offsets: Vec<Vec2> = vec![];
+        match self {
+            Self::Box => {
+                for y in 0..size {
+                    for x in 0..size {
+                        offsets.push(grid(x, y));
+                    }
+                }
+            },
+            Self::Rod => {
+                for x in 0..size * 2 {
+                    offsets.push(grid(x, 0));
+                }
+            },
+            Self::LShape => {
+                let thickness: usize = (size / 3).max(1);
+                for y in 0..size {
+                    for x in 0..size {
+                        if x < thickness || y >= size - thickness {
+                            offsets.push(grid(x, y));
+                        }
+                    }
+                }
+            },
+            Self::Gear => {
+                // hexagonally packed disc with a tooth on every other object of its rim
+                let radius: f32 = size as f32 * spacing * 0.5;
+                let row_height: f32 = spacing * 3f32.sqrt() * 0.5;
+                let rows: i32 = (radius / row_height) as i32;
+                for row in -rows..=rows {
+                    let shift: f32 = if row % 2 == 0 {0.0} else {spacing * 0.5};
+                    let columns: i32 = (radius / spacing) as i32 + 1;
+                    for column in -columns..=columns {
+                        let offset: Vec2 = Vec2 {x: column as f32 * spacing + shift, y: row as f32 * row_height};
+                        if offset.len() <= radius {
+                            offsets.push(offset);
+                        }
+                    }
+                }
+                let rim: usize = ((std::f32::consts::TAU * (radius + spacing) / spacing) as usize).max(6);
+                for tooth in (0..rim).step_by(2) {
+                    let angle: f32 = std::f32::consts::TAU * tooth as f32 / rim as f32;
+                    offsets.push(Vec2 {x: angle.cos(), y: angle.sin()} * (radius + spacing));
+                }
+            },
+        }
+        offsets
+    }
+}
+
+// cluster of objects pulled toward the best fitting rotation and translation of its rest shape
+pub struct RigidBody {
+    // object indices, kept in sync when objects are removed or reordered
+    pub indices: Vec<usize>,
+    // rest position of every member in the shape
+    pub rest_offsets: Vec<Vec2>,
+    // fraction of the way to the matched shape members move every substep, below 1 is semi-rigid
+    pub stiffness: f32,
+}
+
+fn center_of_mass(positions: &[Vec2], masses: &[f32]) -> Vec2 {
+    let total_mass: f32 = masses.iter().sum();
+    positions.iter().zip(masses.iter())
+        .fold(Vec2::zero(), |sum, (&position, &mass)| sum + position * mass) / total_mass.max(f32::EPSILON)
+}
+
+impl RigidBody {
+    // goal positions of the members, the rest shape rotated and moved onto the current positions,
+    // both centers are recalculated so the shape stays consistent after members are removed
+    pub fn goals(&self, positions: &[Vec2], masses: &[f32]) -> Vec<Vec2> {
+        let center: Vec2 = center_of_mass(positions, masses);
+        let rest_center: Vec2 = center_of_mass(&self.rest_offsets, masses);
+
+        // the rotation maximizing the mass weighted alignment of rest and current offsets
+        let (mut sin, mut cos): (f32, f32) = (0.0, 0.0);
+        for ((&position, &rest), &mass) in positions.iter().zip(self.rest_offsets.iter()).zip(masses.iter()) {
+            let (offset, rest): (Vec2, Vec2) = (position - center, rest - rest_center);
+            sin += mass * (rest.x * offset.y - rest.y * offset.x);
+            cos += mass * (rest.x * offset.x + rest.y * offset.y);
+        }
+        let (sin, cos): (f32, f32) = sin.atan2(cos).sin_cos();
+        self.rest_offsets.iter()
+            .map(|&rest| {
+                let rest: Vec2 = rest - rest_center;
+                center + Vec2 {x: rest.x * cos - rest.y * sin, y: rest.x * sin + rest.y * cos}
+            })
+            .collect()
+    }
+}
+
+pub struct RigidBodies {
+    pub bodies: Vec<RigidBody>,
+
+    // spawning
+    pub spawn_shape: RigidShape,
+    // objects across
+    pub spawn_size: usize,
+    pub spawn_stiffness: f32,
+}
+
+impl RigidBodies {
+    pub fn default() -> Self {
+        Self {
+            bodies: vec![],
+            spawn_shape: RigidShape::Box,
+            spawn_size: 5,
+            spawn_stiffness: 1.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+    }
+
+    // keeps the member indices pointing at the same objects after the object at index was removed,
+    // the rest of the shape stays as it was
+    pub fn remove_object(&mut self, index: usize) {
+        for body in self.bodies.iter_mut() {
+            if let Some(member) = verlet::index_removed(&mut body.indices, index) {
+                body.indices.remove(member);
+                body.rest_offsets.remove(member);
+            }
+        }
+        self.bodies.retain(|body| body.indices.len() >= 2);
+    }
+
+    // object order[i] became object i
+    pub fn permute(&mut self, order: &[usize]) {
+        let new_indices: Vec<usize> = verlet::inverse_order(order);
+        for body in self.bodies.iter_mut() {
+            for i in body.indices.iter_mut() {
+                *i = new_indices[*i];
+            }
+        }
+    }
+}
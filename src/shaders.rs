@@ -1,16 +1,17 @@
 
-use macroquad::prelude::*;
+use macroquad::miniquad::Pipeline;
+use crate::batch::create_pipeline;
 
 pub const DEFAULT_FRAGMENT_SHADER: &'static str = "\
 #version 100
 precision lowp float;
 
-// verlet object properties
-uniform lowp vec2 pos_old;
-uniform lowp vec2 pos_curr;
-uniform lowp vec2 acceleration;
-uniform lowp float radius;
-uniform lowp float temperature;
+// verlet object properties (per-instance)
+varying vec2 pos_old;
+varying vec2 pos_curr;
+varying float radius;
+varying float temperature;
+varying vec4 color;
 
 // rendering coordinates (object-space and screen-space)
 varying vec2 uv;
@@ -26,16 +27,36 @@ void main() {
 }
 ";
 
+pub const OBJECT_COLOR_FRAGMENT_SHADER: &'static str = "\
+#version 100
+precision lowp float;
+
+// verlet object properties (per-instance)
+varying vec2 pos_old;
+varying vec2 pos_curr;
+varying float radius;
+varying float temperature;
+varying vec4 color;
+
+// rendering coordinates (object-space and screen-space)
+varying vec2 uv;
+varying vec3 pos;
+
+void main() {
+    gl_FragColor = color;
+}
+";
+
 pub const FIRE_FRAGMENT_SHADER: &'static str = "\
 #version 100
 precision lowp float;
 
-// verlet object properties
-uniform lowp vec2 pos_old;
-uniform lowp vec2 pos_curr;
-uniform lowp vec2 acceleration;
-uniform lowp float radius;
-uniform lowp float temperature;
+// verlet object properties (per-instance)
+varying vec2 pos_old;
+varying vec2 pos_curr;
+varying float radius;
+varying float temperature;
+varying vec4 color;
 
 // rendering coordinates (object-space and screen-space)
 varying vec2 uv;
@@ -55,12 +76,12 @@ pub const WATER_FRAGMENT_SHADER: &'static str = "\
 #version 100
 precision lowp float;
 
-// verlet object properties
-uniform lowp vec2 pos_old;
-uniform lowp vec2 pos_curr;
-uniform lowp vec2 acceleration;
-uniform lowp float radius;
-uniform lowp float temperature;
+// verlet object properties (per-instance)
+varying vec2 pos_old;
+varying vec2 pos_curr;
+varying float radius;
+varying float temperature;
+varying vec4 color;
 
 // rendering coordinates (object-space and screen-space)
 varying vec2 uv;
@@ -80,12 +101,12 @@ pub const DENSITY_FRAGMENT_SHADER: &'static str = "\
 #version 100
 precision lowp float;
 
-// verlet object properties
-uniform lowp vec2 pos_old;
-uniform lowp vec2 pos_curr;
-uniform lowp vec2 acceleration;
-uniform lowp float radius;
-uniform lowp float temperature;
+// verlet object properties (per-instance)
+varying vec2 pos_old;
+varying vec2 pos_curr;
+varying float radius;
+varying float temperature;
+varying vec4 color;
 
 // rendering coordinates (object-space and screen-space)
 varying vec2 uv;
@@ -101,32 +122,45 @@ void main() {
 
 pub const DEFAULT_VERTEX_SHADER: &'static str = "\
 #version 100
-precision lowp float;
+precision highp float;
+
+// unit circle mesh
+attribute vec2 position;
 
-attribute vec3 position;
-attribute vec2 texcoord;
+// verlet object properties (per-instance attributes)
+attribute vec2 inst_pos_curr;
+attribute vec2 inst_pos_old;
+attribute float inst_radius;
+attribute float inst_temperature;
+attribute vec4 inst_color;
 
+// verlet object properties passed on to the fragment shader
+varying vec2 pos_old;
+varying vec2 pos_curr;
+varying float radius;
+varying float temperature;
+varying vec4 color;
+
+// rendering coordinates (object-space and screen-space)
 varying vec2 uv;
 varying vec3 pos;
 
-uniform mat4 Model;
 uniform mat4 Projection;
 
 void main() {
-    gl_Position = Projection * Model * vec4(position, 1);
-    uv = texcoord;
+    gl_Position = Projection * vec4(inst_pos_curr + position * inst_radius, 0.0, 1.0);
+    uv = position;
     pos = gl_Position.xyz;
+
+    pos_old = inst_pos_old;
+    pos_curr = inst_pos_curr;
+    radius = inst_radius;
+    temperature = inst_temperature;
+    color = inst_color;
 }
 ";
 
 pub struct ShaderContext {
-    // shader uniforms usage
-    pub use_uniform_pos_old: bool,
-    pub use_uniform_pos_curr: bool,
-    pub use_uniform_acceleration: bool,
-    pub use_uniform_radius: bool,
-    pub use_uniform_temperature: bool,
-
     // ui variables
     pub use_shaders: bool,
     pub auto_reload_shaders: bool,
@@ -134,30 +168,13 @@ pub struct ShaderContext {
     // shader variables
     pub fragment_shader: String,
     pub vertex_shader: String,
-    pipeline_params: PipelineParams,
-    uniforms: Vec<(String, UniformType)>,
-    pub material: Material,
+    pub pipeline: Pipeline,
     pub shader_error: String,
 }
 
 impl ShaderContext {
     pub fn default() -> Self{
-        let pipeline_params: PipelineParams = PipelineParams::default();
-        let uniforms: Vec<(String, UniformType)> = vec![
-            ("pos_curr".to_string(), UniformType::Float2),
-            ("pos_old".to_string(), UniformType::Float2),
-            ("acceleration".to_string(), UniformType::Float2),
-            ("radius".to_string(), UniformType::Float1),
-            ("temperature".to_string(), UniformType::Float1),
-        ];
         Self {
-            // shader uniforms usage
-            use_uniform_pos_old: false,
-            use_uniform_pos_curr: false,
-            use_uniform_acceleration: false,
-            use_uniform_radius: false,
-            use_uniform_temperature: false,
-
             // ui variables
             use_shaders: false,
             auto_reload_shaders: true,
@@ -165,39 +182,26 @@ impl ShaderContext {
             // shader variables
             fragment_shader: DEFAULT_FRAGMENT_SHADER.to_string(),
             vertex_shader: DEFAULT_VERTEX_SHADER.to_string(),
-            pipeline_params,
-            uniforms: uniforms.clone(),
-            material: load_material(
-                &DEFAULT_VERTEX_SHADER.to_string(),
-                &DEFAULT_FRAGMENT_SHADER.to_string(),
-                MaterialParams {
-                    uniforms: uniforms.clone(),
-                    pipeline_params,
-                    textures: vec![],
-                },
+            pipeline: create_pipeline(
+                DEFAULT_VERTEX_SHADER,
+                DEFAULT_FRAGMENT_SHADER,
             ).unwrap(),
             shader_error: String::new(),
         }
     }
 
     pub fn reload_shaders(&mut self) {
-        match load_material(
+        match create_pipeline(
             self.vertex_shader.as_str(),
             self.fragment_shader.as_str(),
-            MaterialParams {
-                uniforms: self.uniforms.clone(),
-                pipeline_params: self.pipeline_params,
-                textures: vec![],
-            },
         ) {
-            Ok(mat) => {
-                self.material.delete();
-                self.material = mat;
+            Ok(pipeline) => {
+                self.pipeline = pipeline;
                 self.shader_error = String::new();
             },
             Err(error) => {
-                self.shader_error = error.to_string();
+                self.shader_error = error;
             },
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,469 @@
+use macroquad::prelude::{draw_circle, draw_triangle, vec2, Color};
+use rand::Rng;
+use crate::vector::Vec2;
+
+// most vertices a convex polygon can have, also the size of a hull
+pub const MAX_POLYGON_VERTICES: usize = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShapeKind {
+    Circle,
+    Capsule,
+    Box,
+    Polygon,
+}
+
+impl ShapeKind {
+    pub const ALL: [Self; 4] = [
+        Self::Circle,
+        Self::Capsule,
+        Self::Box,
+        Self::Polygon,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Circle => "Circle",
+            Self::Capsule => "Capsule",
+            Self::Box => "Box",
+            Self::Polygon => "Convex polygon",
+        }
+    }
+}
+
+// collision shape of an object, sizes are fractions of the object radius, which stays the
+// bounding radius the broadphase works with, so shapes scale along with it
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shape {
+    Circle,
+    // segment along the local x axis, rounded so that its ends reach the radius
+    Capsule {half_length: f32},
+    // rectangle with its corners at most the radius away from the center
+    Box {half_extents: Vec2},
+    // convex polygon from the solver's polygon list
+    Polygon {index: u32},
+}
+
+// convex polygon centered on its centroid, vertices within the unit circle
+#[derive(Clone, Copy, PartialEq)]
+pub struct ConvexPolygon {
+    pub vertices: [Vec2; MAX_POLYGON_VERTICES],
+    pub count: usize,
+}
+
+// shape placed in the world, a convex polygon (a point for circles and a segment for capsules)
+// rounded by a radius
+pub struct Hull {
+    pub vertices: [Vec2; MAX_POLYGON_VERTICES],
+    pub count: usize,
+    pub rounding: f32,
+    // bounding radius of the object
+    pub radius: f32,
+}
+
+// penetration between two hulls, the normal pushes the first one out of the second one
+pub struct HullContact {
+    pub normal: Vec2,
+    pub depth: f32,
+    pub point: Vec2,
+}
+
+impl ConvexPolygon {
+    // takes the vertices in order around the polygon, centers them on their centroid and
+    // scales them to touch the unit circle, extra vertices are dropped
+    pub fn new(points: &[Vec2]) -> Self {
+        let count: usize = points.len().min(MAX_POLYGON_VERTICES);
+        let points: &[Vec2] = &points[..count];
+        let mut area: f32 = 0.0;
+        let mut centroid: Vec2 = Vec2::zero();
+        for (i, &point) in points.iter().enumerate() {
+            let next: Vec2 = points[(i + 1) % count];
+            let cross: f32 = point.cross(next);
+            area += cross;
+            centroid += (point + next) * cross;
+        }
+        if area.abs() > f32::EPSILON {
+            centroid = centroid / (area * 3.0);
+        }
+        let mut vertices: [Vec2; MAX_POLYGON_VERTICES] = [Vec2::zero(); MAX_POLYGON_VERTICES];
+        for (vertex, &point) in vertices.iter_mut().zip(points.iter()) {
+            *vertex = point - centroid;
+        }
+        let scale: f32 = vertices.iter().map(|vertex| vertex.len()).fold(0.0, f32::max);
+        if scale > f32::EPSILON {
+            for vertex in vertices.iter_mut() {
+                *vertex = *vertex / scale;
+            }
+        }
+        Self {vertices, count}
+    }
+
+    pub fn regular(sides: usize) -> Self {
+        let sides: usize = sides.clamp(3, MAX_POLYGON_VERTICES);
+        let points: Vec<Vec2> = (0..sides)
+            .map(|i| Vec2 {x: 1.0, y: 0.0}.rotated(std::f32::consts::TAU * i as f32 / sides as f32))
+            .collect();
+        Self::new(&points)
+    }
+
+    // vertices on the unit circle, each one at a random angle within its share of the turn,
+    // which keeps the polygon convex and its edges from getting too short
+    pub fn random(sides: usize, rng: &mut impl Rng) -> Self {
+        let sides: usize = sides.clamp(3, MAX_POLYGON_VERTICES);
+        let points: Vec<Vec2> = (0..sides)
+            .map(|i| {
+                let angle: f32 = (i as f32 + rng.gen_range(0.0..0.7)) * std::f32::consts::TAU / sides as f32;
+                Vec2 {x: 1.0, y: 0.0}.rotated(angle)
+            })
+            .collect();
+        Self::new(&points)
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices[..self.count]
+    }
+}
+
+impl Shape {
+    pub fn is_circle(&self) -> bool {
+        matches!(self, Self::Circle)
+    }
+
+    // aspect is the width over the length for capsules and boxes
+    pub fn capsule(aspect: f32) -> Self {
+        Self::Capsule {half_length: 1.0 - aspect.clamp(0.0, 1.0)}
+    }
+
+    pub fn rectangle(aspect: f32) -> Self {
+        let aspect: f32 = aspect.clamp(0.0, 1.0);
+        let diagonal: f32 = (1.0 + aspect * aspect).sqrt();
+        Self::Box {half_extents: Vec2 {x: 1.0 / diagonal, y: aspect / diagonal}}
+    }
+
+    pub fn hull(&self, position: Vec2, angle: f32, radius: f32, polygons: &[ConvexPolygon]) -> Hull {
+        let mut hull: Hull = Hull {
+            vertices: [position; MAX_POLYGON_VERTICES],
+            count: 1,
+            rounding: 0.0,
+            radius,
+        };
+        let mut place = |local: &[Vec2]| {
+            for (vertex, &local) in hull.vertices.iter_mut().zip(local.iter()) {
+                *vertex = position + local.rotated(angle) * radius;
+            }
+            hull.count = local.len();
+        };
+        match *self {
+            Self::Circle => hull.rounding = radius,
+            Self::Capsule {half_length} => {
+                place(&[Vec2 {x: -half_length, y: 0.0}, Vec2 {x: half_length, y: 0.0}]);
+                hull.rounding = (1.0 - half_length) * radius;
+            },
+            Self::Box {half_extents: Vec2 {x, y}} => {
+                place(&[Vec2 {x: -x, y: -y}, Vec2 {x, y: -y}, Vec2 {x, y}, Vec2 {x: -x, y}]);
+            },
+            Self::Polygon {index} => {
+                place(polygons[index as usize].vertices());
+            },
+        }
+        hull
+    }
+
+    // moment of inertia over the mass and the radius squared, the shapes are uniform plates
+    pub fn inertia_factor(&self, polygons: &[ConvexPolygon]) -> f32 {
+        match *self {
+            Self::Circle => 0.5,
+            // approximated by its bounding rectangle
+            Self::Capsule {half_length} => {
+                let rounding: f32 = 1.0 - half_length;
+                (4.0 + 4.0 * rounding * rounding) / 12.0
+            },
+            Self::Box {half_extents} => (half_extents.x * half_extents.x + half_extents.y * half_extents.y) / 3.0,
+            Self::Polygon {index} => {
+                let vertices: &[Vec2] = polygons[index as usize].vertices();
+                let (mut numerator, mut denominator): (f32, f32) = (0.0, 0.0);
+                for (i, &vertex) in vertices.iter().enumerate() {
+                    let next: Vec2 = vertices[(i + 1) % vertices.len()];
+                    let cross: f32 = vertex.cross(next).abs();
+                    numerator += cross * (vertex.dot(vertex) + vertex.dot(next) + next.dot(next));
+                    denominator += cross;
+                }
+                numerator / (6.0 * denominator.max(f32::EPSILON))
+            },
+        }
+    }
+
+    pub fn draw(&self, position: Vec2, angle: f32, radius: f32, color: Color, polygons: &[ConvexPolygon]) {
+        let hull: Hull = self.hull(position, angle, radius, polygons);
+        let vertices: &[Vec2] = hull.vertices();
+        if hull.rounding > 0.0 {
+            for vertex in vertices {
+                draw_circle(vertex.x, vertex.y, hull.rounding, color);
+            }
+        }
+        if let [start, end] = vertices {
+            let side: Vec2 = (*end - *start).perpendicular() * (hull.rounding / (*end - *start).len().max(f32::EPSILON));
+            let corners: [Vec2; 4] = [*start + side, *end + side, *end - side, *start - side];
+            draw_triangle(vec2(corners[0].x, corners[0].y), vec2(corners[1].x, corners[1].y), vec2(corners[2].x, corners[2].y), color);
+            draw_triangle(vec2(corners[0].x, corners[0].y), vec2(corners[2].x, corners[2].y), vec2(corners[3].x, corners[3].y), color);
+        }
+        if vertices.len() >= 3 {
+            for i in 1..vertices.len() - 1 {
+                let (a, b, c): (Vec2, Vec2, Vec2) = (vertices[0], vertices[i], vertices[i + 1]);
+                draw_triangle(vec2(a.x, a.y), vec2(b.x, b.y), vec2(c.x, c.y), color);
+            }
+        }
+    }
+}
+
+impl Hull {
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices[..self.count]
+    }
+
+    // smallest and largest projection onto the axis, rounding included
+    fn project(&self, axis: Vec2) -> (f32, f32) {
+        let (min, max): (f32, f32) = self.vertices().iter()
+            .map(|vertex| vertex.dot(axis))
+            .fold((f32::MAX, f32::MIN), |(min, max), projection| (min.min(projection), max.max(projection)));
+        (min - self.rounding, max + self.rounding)
+    }
+
+    // middle of the vertices reaching furthest by the given measure, so that a whole edge lying
+    // flat against something pushes on its middle instead of on one of its ends,
+    // along with how far that is with the rounding included
+    pub fn furthest(&self, reach: impl Fn(Vec2) -> f32) -> (Vec2, f32) {
+        let furthest: f32 = self.vertices().iter().map(|&vertex| reach(vertex)).fold(f32::MIN, f32::max);
+        let tolerance: f32 = self.radius * 0.01;
+        let (sum, count): (Vec2, usize) = self.vertices().iter()
+            .filter(|&&vertex| reach(vertex) >= furthest - tolerance)
+            .fold((Vec2::zero(), 0), |(sum, count), &vertex| (sum + vertex, count + 1));
+        (sum / count as f32, furthest + self.rounding)
+    }
+
+    // point of the polygon outline (the point or the segment for rounded hulls) closest to point
+    fn closest_point(&self, point: Vec2) -> Vec2 {
+        let vertices: &[Vec2] = self.vertices();
+        if vertices.len() == 1 {
+            return vertices[0];
+        }
+        let mut closest: Vec2 = vertices[0];
+        let mut closest_distance: f32 = f32::MAX;
+        for (i, &start) in vertices.iter().enumerate() {
+            let end: Vec2 = vertices[(i + 1) % vertices.len()];
+            let edge: Vec2 = end - start;
+            let t: f32 = ((point - start).dot(edge) / edge.dot(edge).max(f32::EPSILON)).clamp(0.0, 1.0);
+            let candidate: Vec2 = start + edge * t;
+            let distance: f32 = (point - candidate).len();
+            if distance < closest_distance {
+                closest = candidate;
+                closest_distance = distance;
+            }
+        }
+        closest
+    }
+
+    // unit normals of the polygon edges, one for a segment and none for a point
+    fn edge_normals(&self) -> impl Iterator<Item = Vec2> + '_ {
+        let vertices: &[Vec2] = self.vertices();
+        let edge_count: usize = match vertices.len() {
+            1 => 0,
+            2 => 1,
+            count => count,
+        };
+        (0..edge_count).filter_map(move |i| {
+            let edge: Vec2 = vertices[(i + 1) % vertices.len()] - vertices[i];
+            let length: f32 = edge.len();
+            (length > f32::EPSILON).then(|| edge.perpendicular() / length)
+        })
+    }
+}
+
+// separating axis test between two rounded convex polygons, the axes are the edge normals of
+// both and the directions from every vertex to the closest point of the other hull, which covers
+// the rounded corners, the contact point is in the middle of the touching features
+pub fn hull_contact(hull_1: &Hull, hull_2: &Hull) -> Option<HullContact> {
+    // edge normals and vertex directions of both hulls, on the stack as this runs for every pair
+    let mut axes: [Vec2; 4 * MAX_POLYGON_VERTICES] = [Vec2::zero(); 4 * MAX_POLYGON_VERTICES];
+    let mut axis_count: usize = 0;
+    for axis in hull_1.edge_normals().chain(hull_2.edge_normals()) {
+        axes[axis_count] = axis;
+        axis_count += 1;
+    }
+    for (hull, other) in [(hull_1, hull_2), (hull_2, hull_1)] {
+        for &vertex in hull.vertices() {
+            let offset: Vec2 = vertex - other.closest_point(vertex);
+            let distance: f32 = offset.len();
+            if distance > f32::EPSILON {
+                axes[axis_count] = offset / distance;
+                axis_count += 1;
+            }
+        }
+    }
+    if axis_count == 0 {
+        // two points at the same spot, any axis works
+        axes[0] = Vec2 {x: 0.0, y: -1.0};
+        axis_count = 1;
+    }
+
+    let mut normal: Vec2 = Vec2::zero();
+    let mut depth: f32 = f32::MAX;
+    for &axis in &axes[..axis_count] {
+        let (min_1, max_1): (f32, f32) = hull_1.project(axis);
+        let (min_2, max_2): (f32, f32) = hull_2.project(axis);
+        // overlap when pushing the first hull along the axis or against it
+        let (along, against): (f32, f32) = (max_2 - min_1, max_1 - min_2);
+        let overlap: f32 = along.min(against);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if overlap < depth {
+            depth = overlap;
+            normal = if along < against {axis} else {-axis};
+        }
+    }
+
+    // features of both hulls facing each other, vertices within a small tolerance of the deepest one
+    let tolerance: f32 = (hull_1.radius + hull_2.radius) * 0.01;
+    let tangent: Vec2 = normal.perpendicular();
+    let feature = |hull: &Hull, direction: Vec2| -> (f32, f32, f32) {
+        let deepest: f32 = hull.vertices().iter().map(|vertex| vertex.dot(direction)).fold(f32::MIN, f32::max);
+        let (mut min, mut max): (f32, f32) = (f32::MAX, f32::MIN);
+        for vertex in hull.vertices().iter().filter(|vertex| vertex.dot(direction) >= deepest - tolerance) {
+            min = min.min(vertex.dot(tangent));
+            max = max.max(vertex.dot(tangent));
+        }
+        (deepest + hull.rounding, min, max)
+    };
+    let (surface_1, min_1, max_1): (f32, f32, f32) = feature(hull_1, -normal);
+    let (surface_2, min_2, max_2): (f32, f32, f32) = feature(hull_2, normal);
+    // middle of where the features overlap along the tangent, or of the gap between them
+    let along_tangent: f32 = (min_1.max(min_2) + max_1.min(max_2)) * 0.5;
+    let along_normal: f32 = (-surface_1 + surface_2) * 0.5;
+    Some(HullContact {
+        normal,
+        depth,
+        point: normal * along_normal + tangent * along_tangent,
+    })
+}
+
+// where a contact touches a body relative to its center, and how hard the body is to move and turn
+#[derive(Clone, Copy)]
+pub struct ContactArm {
+    pub offset: Vec2,
+    pub inverse_mass: f32,
+    pub inverse_inertia: f32,
+}
+
+impl ContactArm {
+    // walls and other things that don't move
+    pub fn fixed() -> Self {
+        Self {
+            offset: Vec2::zero(),
+            inverse_mass: 0.0,
+            inverse_inertia: 0.0,
+        }
+    }
+
+    // generalized inverse mass along the direction
+    fn weight(&self, direction: Vec2) -> f32 {
+        let arm: f32 = self.offset.cross(direction);
+        self.inverse_mass + arm * arm * self.inverse_inertia
+    }
+
+    // how fast the touching point moves, given the velocity and angular velocity of the body
+    pub fn point_velocity(&self, velocity: Vec2, angular_velocity: f32) -> Vec2 {
+        velocity + self.offset.perpendicular() * angular_velocity
+    }
+}
+
+// position based correction moving the touching points of two bodies apart by the distance along
+// the direction, split by the generalized inverse masses so that off center contacts turn the
+// bodies, returns the shifts and the turns of both bodies
+pub fn correction(direction: Vec2, distance: f32, arm_1: ContactArm, arm_2: ContactArm) -> (Vec2, Vec2, f32, f32) {
+    let impulse: f32 = distance / (arm_1.weight(direction) + arm_2.weight(direction)).max(f32::EPSILON);
+    (
+        direction * (impulse * arm_1.inverse_mass),
+        direction * (-impulse * arm_2.inverse_mass),
+        arm_1.offset.cross(direction) * impulse * arm_1.inverse_inertia,
+        -arm_2.offset.cross(direction) * impulse * arm_2.inverse_inertia,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const RADIUS: f32 = 10.0;
+
+    fn placed(shape: Shape, x: f32, y: f32, angle: f32, polygons: &[ConvexPolygon]) -> Hull {
+        shape.hull(Vec2 {x, y}, angle, RADIUS, polygons)
+    }
+
+    // squares with sides of radius * sqrt(2), side by side along x
+    #[test]
+    fn overlapping_boxes() {
+        let square: Shape = Shape::rectangle(1.0);
+        let contact: HullContact = hull_contact(&placed(square, 0.0, 0.0, 0.0, &[]), &placed(square, 12.0, 0.0, 0.0, &[])).unwrap();
+        assert!((contact.normal - Vec2 {x: -1.0, y: 0.0}).len() < 1e-4);
+        assert!((contact.depth - (RADIUS * 2f32.sqrt() - 12.0)).abs() < 1e-3);
+        assert!((contact.point.x - 6.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn separated_hulls() {
+        let square: Shape = Shape::rectangle(1.0);
+        assert!(hull_contact(&placed(square, 0.0, 0.0, 0.0, &[]), &placed(square, 15.0, 0.0, 0.0, &[])).is_none());
+        // the corners of the turned square just miss the other one
+        assert!(hull_contact(&placed(square, 0.0, 0.0, 0.0, &[]), &placed(square, 17.5, 0.0, 0.78, &[])).is_none());
+        let capsule: Shape = Shape::capsule(0.5);
+        assert!(hull_contact(&placed(capsule, 0.0, 0.0, 0.0, &[]), &placed(square, 0.0, 13.0, 0.0, &[])).is_none());
+    }
+
+    // capsule lying along x with its rounded side 5 above its axis, box below it
+    #[test]
+    fn capsule_against_box() {
+        let capsule: Hull = placed(Shape::capsule(0.5), 0.0, 0.0, 0.0, &[]);
+        let square: Hull = placed(Shape::rectangle(1.0), 0.0, 11.0, 0.0, &[]);
+        let contact: HullContact = hull_contact(&capsule, &square).unwrap();
+        let expected_depth: f32 = 5.0 - (11.0 - RADIUS / 2f32.sqrt());
+        assert!((contact.normal - Vec2 {x: 0.0, y: -1.0}).len() < 1e-4);
+        assert!((contact.depth - expected_depth).abs() < 1e-3);
+        assert!(contact.point.y > 11.0 - RADIUS / 2f32.sqrt() && contact.point.y < 5.0);
+
+        // the other way around the normal flips
+        let contact: HullContact = hull_contact(&square, &capsule).unwrap();
+        assert!((contact.normal - Vec2 {x: 0.0, y: 1.0}).len() < 1e-4);
+    }
+
+    // polygons with the most vertices give the most axes, edge normals and vertex directions of both
+    #[test]
+    fn polygons_with_the_most_vertices() {
+        let mut rng: StdRng = StdRng::seed_from_u64(3);
+        let mut polygons: Vec<ConvexPolygon> = vec![ConvexPolygon::regular(MAX_POLYGON_VERTICES)];
+        polygons.extend((0..20).map(|_| ConvexPolygon::random(MAX_POLYGON_VERTICES, &mut rng)));
+        assert!(polygons.iter().all(|polygon| polygon.count == MAX_POLYGON_VERTICES));
+
+        for (index, _) in polygons.iter().enumerate() {
+            let shape_1: Shape = Shape::Polygon {index: 0};
+            let shape_2: Shape = Shape::Polygon {index: index as u32};
+            for step in 0..40 {
+                let distance: f32 = step as f32;
+                let angle: f32 = step as f32 * 0.3;
+                let hull_1: Hull = placed(shape_1, 0.0, 0.0, 0.1, &polygons);
+                let hull_2: Hull = placed(shape_2, distance, 0.0, angle, &polygons);
+                match hull_contact(&hull_1, &hull_2) {
+                    Some(contact) => {
+                        assert!((contact.normal.len() - 1.0).abs() < 1e-4);
+                        assert!(contact.depth > 0.0 && contact.depth <= RADIUS * 2.0);
+                    },
+                    // the octagon reaches out to its inner radius in every direction, the random
+                    // polygons have gaps of at most 1.7 eighths of a turn between their vertices
+                    None => assert!(distance >= RADIUS * ((PI / 8.0).cos() + (1.7 * PI / 8.0).cos())),
+                }
+            }
+        }
+    }
+}
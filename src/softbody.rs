@@ -0,0 +1,96 @@
+use macroquad::prelude::{draw_line, draw_triangle, vec2, Color};
+use crate::{vector::Vec2, verlet};
+
+// closed ring of objects held together by springs along its edges and inflated by the gas inside
+pub struct SoftBody {
+    // object indices around the ring, kept in sync when objects are removed or reordered
+    pub indices: Vec<usize>,
+    // rest length of the edge from every member to the next one
+    pub rest_lengths: Vec<f32>,
+    pub rest_area: f32,
+    // fraction of the edge length error corrected every substep
+    pub stiffness: f32,
+    // gas pressure at the rest area, grows as the body gets squeezed (ideal gas)
+    pub pressure: f32,
+    pub color: Color,
+}
+
+impl SoftBody {
+    // signed area of the ring, positive or negative depending on its winding
+    pub fn signed_area(positions: &[Vec2]) -> f32 {
+        let mut area: f32 = 0.0;
+        for (i, &position) in positions.iter().enumerate() {
+            let next: Vec2 = positions[(i + 1) % positions.len()];
+            area += position.x * next.y - next.x * position.y;
+        }
+        area * 0.5
+    }
+
+    // forgets a removed member by joining its two edges, the body breaks apart below 3 members
+    fn remove_member(&mut self, member: usize) {
+        let previous: usize = (member + self.indices.len() - 1) % self.indices.len();
+        self.rest_lengths[previous] += self.rest_lengths[member];
+        self.indices.remove(member);
+        self.rest_lengths.remove(member);
+    }
+
+    pub fn draw(&self, positions: &[Vec2]) {
+        let center: Vec2 = positions.iter().fold(Vec2::zero(), |sum, &position| sum + position) / positions.len() as f32;
+        let mut fill: Color = self.color;
+        fill.a *= 0.5;
+        for (i, &position) in positions.iter().enumerate() {
+            let next: Vec2 = positions[(i + 1) % positions.len()];
+            draw_triangle(
+                vec2(center.x, center.y),
+                vec2(position.x, position.y),
+                vec2(next.x, next.y),
+                fill,
+            );
+            draw_line(position.x, position.y, next.x, next.y, 2.0, self.color);
+        }
+    }
+}
+
+pub struct SoftBodies {
+    pub bodies: Vec<SoftBody>,
+
+    // spawning
+    pub spawn_radius: f32,
+    pub spawn_stiffness: f32,
+    pub spawn_pressure: f32,
+}
+
+impl SoftBodies {
+    pub fn default() -> Self {
+        Self {
+            bodies: vec![],
+            spawn_radius: 60.0,
+            spawn_stiffness: 0.5,
+            spawn_pressure: 5_000.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+    }
+
+    // keeps the member indices pointing at the same objects after the object at index was removed
+    pub fn remove_object(&mut self, index: usize) {
+        for body in self.bodies.iter_mut() {
+            if let Some(member) = verlet::index_removed(&mut body.indices, index) {
+                body.remove_member(member);
+            }
+        }
+        self.bodies.retain(|body| body.indices.len() >= 3);
+    }
+
+    // object order[i] became object i
+    pub fn permute(&mut self, order: &[usize]) {
+        let new_indices: Vec<usize> = verlet::inverse_order(order);
+        for body in self.bodies.iter_mut() {
+            for i in body.indices.iter_mut() {
+                *i = new_indices[*i];
+            }
+        }
+    }
+}
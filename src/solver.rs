@@ -1,19 +1,213 @@
 
-use crate::{vector::Vec2, verlet::VerletObject};
-use macroquad::prelude::{screen_width, screen_height};
+use crate::{vector::Vec2, container::Container, gravity::{GravityWell, GravityZone}, nbody::NBody, forces::Electrostatics, material::{Material, Materials, MATERIAL_COUNT}, fluid::{self, Fluid}, softbody::{SoftBody, SoftBodies}, rigidbody::{RigidBody, RigidBodies}, shape::{self, Shape, ShapeKind, ConvexPolygon, ContactArm, Hull, HullContact}, thermal::{self, WallHeat}, phase::{self, Phase, PhaseChanges, Bond}, verlet::{self, VerletObject, VerletObjects, DEFAULT_OBJECT_COLOR}, grid::{CellGrid, MultiLevelGrid, SpatialHash}};
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use crate::verlet::SharedVerletObjects;
+use macroquad::prelude::Color;
+use macroquad::color::hsl_to_rgb;
 use rand::{rngs::ThreadRng, thread_rng, Rng};
+use std::collections::HashSet;
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use rayon::prelude::*;
 
 // this is to prevent "popcorn effect" and is less effective the more objects there are
 // doubles as a default cell size (for smallest spawnable 1.0 radius objects)
 pub const CELL_SIZE_RADIUS_FACTOR: f32 = 4.0;
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum Broadphase {
+    // flat counting-sort grid
+    Flat,
+    // one flat grid per object size, for scenes mixing very different radii
+    MultiLevel,
+    // hashed grid without bounds, objects collide outside of the window too
+    SpatialHash,
+    // original grid with a heap allocated vector per cell, kept for comparison
+    Nested,
+}
+
+// result of a single collision, the shifts and turns of both objects
+pub struct Contact {
+    // pushes the first object away from the second one
+    pub normal: Vec2,
+    // middle of the overlap
+    pub point: Vec2,
+    pub shift_1: Vec2,
+    pub shift_2: Vec2,
+    // always zero between circles
+    pub turn_1: f32,
+    pub turn_2: f32,
+    pub overlap: f32,
+    // conducted from the first object to the second one
+    pub heat: f32,
+}
+
+// the fields of an object the narrow phase needs
+pub struct ContactBody {
+    pub shape: Shape,
+    pub position: Vec2,
+    pub angle: f32,
+    pub radius: f32,
+    pub rest_radius: f32,
+    pub temperature: f32,
+    pub heat_capacity: f32,
+    // displacements over the last substep
+    pub velocity: Vec2,
+    pub angular_velocity: f32,
+}
+
+impl ContactBody {
+    // object i, at the given image of its position
+    pub fn new(objects: &VerletObjects, i: usize, position: Vec2) -> Self {
+        Self {
+            shape: objects.shape[i],
+            position,
+            angle: objects.angle[i],
+            radius: objects.radius[i],
+            rest_radius: objects.rest_radius[i],
+            temperature: objects.temperature[i],
+            heat_capacity: objects.heat_capacity[i],
+            velocity: objects.position_current[i] - objects.position_old[i],
+            angular_velocity: objects.angle[i] - objects.angle_old[i],
+        }
+    }
+
+    // a single object at its own position, for storage other than VerletObjects
+    pub fn from_object(obj: &VerletObject) -> Self {
+        Self {
+            shape: obj.shape,
+            position: obj.position_current,
+            angle: obj.angle,
+            radius: obj.radius,
+            rest_radius: obj.rest_radius,
+            temperature: obj.temperature,
+            heat_capacity: obj.heat_capacity,
+            velocity: obj.position_current - obj.position_old,
+            angular_velocity: obj.angle - obj.angle_old,
+        }
+    }
+
+    // safety: see Solver::collide_shared
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    unsafe fn shared(objects: SharedVerletObjects, i: usize, position: Vec2) -> Self {
+        Self {
+            shape: *objects.shape.add(i),
+            position,
+            angle: *objects.angle.add(i),
+            radius: *objects.radius.add(i),
+            rest_radius: *objects.rest_radius.add(i),
+            temperature: *objects.temperature.add(i),
+            heat_capacity: *objects.heat_capacity.add(i),
+            velocity: *objects.position_current.add(i) - *objects.position_old.add(i),
+            angular_velocity: *objects.angle.add(i) - *objects.angle_old.add(i),
+        }
+    }
+}
+
+// verlet integration of a single object, resetting the acceleration is left to the caller
+fn integrate(position_current: &mut Vec2, position_old: &mut Vec2, acceleration: Vec2, dt: f32) {
+    let velocity: Vec2 = *position_current - *position_old;
+    *position_old = *position_current;
+    *position_current = *position_current + velocity + acceleration * dt * dt;
+}
+
+// nothing applies torques directly, angles only change through contacts and keep turning
+fn integrate_angle(angle: &mut f32, angle_old: &mut f32) {
+    let angular_velocity: f32 = *angle - *angle_old;
+    *angle_old = *angle;
+    *angle += angular_velocity;
+    // kept within half a turn of zero so it doesn't lose precision
+    if angle.abs() > std::f32::consts::PI {
+        let shift: f32 = (*angle / std::f32::consts::TAU).round() * std::f32::consts::TAU;
+        *angle -= shift;
+        *angle_old -= shift;
+    }
+}
+
+impl Broadphase {
+    pub const ALL: [Self; 4] = [
+        Self::Flat,
+        Self::MultiLevel,
+        Self::SpatialHash,
+        Self::Nested,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Flat => "Flat grid",
+            Self::MultiLevel => "Multi-level grid",
+            Self::SpatialHash => "Spatial hash (unbounded)",
+            Self::Nested => "Nested grid (legacy)",
+        }
+    }
+}
+
 pub struct Solver {
-    pub verlet_objects: Vec<VerletObject>,
+    pub verlet_objects: VerletObjects,
+    // the walls, grids and wrapped axes span this, set from the window every frame in the app
+    pub world_size: Vec2,
     pub cell_size: f32,
+    pub broadphase: Broadphase,
+    pub grid: CellGrid,
+    pub multi_level_grid: MultiLevelGrid,
+    pub spatial_hash: SpatialHash,
     pub cell_grid: Vec<Vec<Vec<usize>>>,
+    pub sort_objects_by_cell: bool,
+    pub solve_pairs_once: bool,
+
+    // multithreading, only available in native builds with the "parallel" feature
+    pub parallel: bool,
+    pub thread_count: usize,
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    thread_pool: Option<(usize, rayon::ThreadPool)>,
+
+    // number of narrow phase checks during the last substep
+    pub pair_checks: usize,
+
+    // delta time of the last substep
+    pub last_dt: f32,
+    // simulated time, drives the container motion
+    pub time: f32,
 
     pub gravity: Vec2,
+    pub gravity_wells: Vec<GravityWell>,
+    pub gravity_zones: Vec<GravityZone>,
+    // mutual gravitation between objects
+    pub n_body: NBody,
+    // charge of the objects and coulomb forces between them
+    pub electrostatics: Electrostatics,
+    // cohesion between objects and adhesion to walls, per material
+    pub materials: Materials,
+    // objects of fluid materials behave as a liquid
+    pub fluid: Fluid,
+    // rings of objects held together by springs and inflated by pressure
+    pub soft_bodies: SoftBodies,
+    // clusters of objects keeping their shape
+    pub rigid_bodies: RigidBodies,
+    // melting, freezing, boiling and condensing, with the bonds holding frozen objects together
+    pub phase_changes: PhaseChanges,
+    // coulomb friction coefficient between objects and against the window walls, turns objects so they roll
+    pub friction: f32,
+    // grid with the interaction cutoff as its cell size, for forces between nearby pairs
+    pub force_grid: CellGrid,
+    // same for the unbounded world of the spatial hash broadphase
+    pub force_hash: SpatialHash,
     pub spawn_radius: f32,
+    pub spawn_color: Color,
+    pub spawn_color_random_hue: bool,
+    pub spawn_charge: f32,
+    // every other spawned object gets the opposite charge, keeping the scene neutral
+    pub spawn_charge_alternate: bool,
+    pub spawn_material: u8,
+    pub spawn_shape: ShapeKind,
+    // width over length of capsules and boxes
+    pub spawn_shape_aspect: f32,
+    pub spawn_polygon_sides: usize,
+    // irregular polygons instead of regular ones, a new one for every object
+    pub spawn_polygon_random: bool,
+    // convex polygons the objects' shapes point into, one per object
+    pub polygons: Vec<ConvexPolygon>,
+    // slots of removed objects' polygons, reused by the next ones spawned
+    free_polygons: Vec<u32>,
 
     pub spawn_safety_radius_factor: f32,
     pub spawn_safety_iterations: usize,
@@ -21,11 +215,26 @@ pub struct Solver {
     pub stabilize_on_spawn: bool,
     pub stabilize_on_oob: bool,
 
+    // objects further than the margin (plus their radius) outside of the window are removed
+    pub kill_oob_objects: bool,
+    pub kill_region_margin: f32,
+
     pub min_object_count: usize,
     pub min_object_count_enforced: bool,
     pub max_object_count: usize,
     pub max_object_count_enforced: bool,
 
+    // periodic boundaries, objects leaving one side reappear on the other,
+    // a wrapped axis ignores the constraints of its sides
+    pub wrap_x: bool,
+    pub wrap_y: bool,
+    // world size along the wrapped axes (zero otherwise) for the narrow phase, which may
+    // run on other threads where the window size can't be queried
+    wrap_size: Vec2,
+
+    // shape objects are kept in, the window uses the per-side settings below
+    pub container: Container,
+
     pub apply_constraint_bottom: bool,
     pub apply_constraint_top: bool,
     pub apply_constraint_left: bool,
@@ -36,21 +245,26 @@ pub struct Solver {
     pub apply_bounce_left: bool,
     pub apply_bounce_right: bool,
 
-    pub apply_temperature_bottom: f32,
-    pub apply_temperature_top: f32,
-    pub apply_temperature_left: f32,
-    pub apply_temperature_right: f32,
+    pub wall_heat_bottom: WallHeat,
+    pub wall_heat_top: WallHeat,
+    pub wall_heat_left: WallHeat,
+    pub wall_heat_right: WallHeat,
 
     pub accelerate_on_temperature: bool,
     pub temperature_acceleration_power: f32,
-    pub heat_transfer_factor: f32,
-    pub heat_loss_factor: f32,
+    // conductance of a contact per unit of the smaller radius, heat per second per degree,
+    // also used for contacts with fixed temperature walls
+    pub conductivity: f32,
+    // objects spawn at the ambient temperature and lose heat to it through convection
+    pub ambient_temperature: f32,
+    // heat per second per degree per unit of circumference lost to the ambient temperature
+    pub convection: f32,
 }
 
 impl Solver {
-    pub fn new() -> Self {
-        let grid_width: usize = (screen_width().ceil() / CELL_SIZE_RADIUS_FACTOR) as usize;
-        let grid_height: usize = (screen_height().ceil() / CELL_SIZE_RADIUS_FACTOR) as usize;
+    pub fn new(world_size: Vec2) -> Self {
+        let grid_width: usize = (world_size.x.ceil() / CELL_SIZE_RADIUS_FACTOR) as usize;
+        let grid_height: usize = (world_size.y.ceil() / CELL_SIZE_RADIUS_FACTOR) as usize;
         let mut grid: Vec<Vec<Vec<usize>>> = Vec::with_capacity(grid_height);
         for i in 0..grid_height {
             grid.push(Vec::with_capacity(grid_width));
@@ -59,15 +273,55 @@ impl Solver {
             }
         }
         Self {
-            verlet_objects: vec![],
+            verlet_objects: VerletObjects::new(),
+            world_size,
             cell_size: CELL_SIZE_RADIUS_FACTOR,
+            broadphase: Broadphase::Flat,
+            grid: CellGrid::new(),
+            multi_level_grid: MultiLevelGrid::new(),
+            spatial_hash: SpatialHash::new(),
             cell_grid: grid,
+            sort_objects_by_cell: false,
+            solve_pairs_once: true,
+
+            parallel: Self::parallel_available(),
+            thread_count: std::thread::available_parallelism().map_or(1, |count| count.get()),
+            #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+            thread_pool: None,
+
+            pair_checks: 0,
+
+            last_dt: 0.0,
+            time: 0.0,
 
             gravity: Vec2 {
                 x: 0.0,
                 y: 1_000.0,
             },
+            gravity_wells: vec![],
+            gravity_zones: vec![],
+            n_body: NBody::default(),
+            electrostatics: Electrostatics::default(),
+            materials: Materials::default(),
+            fluid: Fluid::default(),
+            soft_bodies: SoftBodies::default(),
+            rigid_bodies: RigidBodies::default(),
+            phase_changes: PhaseChanges::default(),
+            friction: 0.0,
+            force_grid: CellGrid::new(),
+            force_hash: SpatialHash::new(),
             spawn_radius: 10.0,
+            spawn_color: DEFAULT_OBJECT_COLOR,
+            spawn_color_random_hue: false,
+            spawn_charge: 0.0,
+            spawn_charge_alternate: false,
+            spawn_material: 0,
+            spawn_shape: ShapeKind::Circle,
+            spawn_shape_aspect: 0.5,
+            spawn_polygon_sides: 5,
+            spawn_polygon_random: false,
+            polygons: vec![],
+            free_polygons: vec![],
 
             spawn_safety_radius_factor: 1.0,
             spawn_safety_iterations: 100,
@@ -75,11 +329,20 @@ impl Solver {
             stabilize_on_spawn: false,
             stabilize_on_oob: true,
 
+            kill_oob_objects: true,
+            kill_region_margin: 0.0,
+
             min_object_count: 500,
             min_object_count_enforced: false,
             max_object_count: 5000,
             max_object_count_enforced: false,
 
+            wrap_x: false,
+            wrap_y: false,
+            wrap_size: Vec2::zero(),
+
+            container: Container::new(world_size),
+
             apply_constraint_bottom: true,
             apply_constraint_top: true,
             apply_constraint_left: true,
@@ -90,35 +353,54 @@ impl Solver {
             apply_bounce_left: true,
             apply_bounce_right: true,
 
-            apply_temperature_bottom: 0.0,
-            apply_temperature_top: 0.0,
-            apply_temperature_left: 0.0,
-            apply_temperature_right: 0.0,
+            wall_heat_bottom: WallHeat::default(),
+            wall_heat_top: WallHeat::default(),
+            wall_heat_left: WallHeat::default(),
+            wall_heat_right: WallHeat::default(),
 
             accelerate_on_temperature: false,
             temperature_acceleration_power: 4.0,
-            heat_transfer_factor: 0.01,
-            heat_loss_factor: 2.0,
+            conductivity: 25.0,
+            ambient_temperature: 0.0,
+            convection: 3.0,
         }
     }
 
+    pub fn parallel_available() -> bool {
+        cfg!(all(feature = "parallel", not(target_arch = "wasm32")))
+    }
+
     pub fn push(&mut self, obj: VerletObject) {
         // optimize cell size (factor to prevent "popcorn effect")
         self.cell_size = self.cell_size.max(obj.radius * CELL_SIZE_RADIUS_FACTOR);
         self.verlet_objects.push(obj);
     }
 
+    // every removal goes through these so that soft and rigid bodies and bonds keep pointing at the right objects
     pub fn remove(&mut self, obj_index: usize) {
+        self.release_polygon(self.verlet_objects.shape[obj_index]);
         self.verlet_objects.remove(obj_index);
+        self.soft_bodies.remove_object(obj_index);
+        self.rigid_bodies.remove_object(obj_index);
+        self.phase_changes.remove_object(obj_index);
     }
 
     pub fn remove_count(&mut self, obj_count: usize) {
-        self.verlet_objects.drain(0..obj_count.min(self.verlet_objects.len()));
+        let obj_count: usize = obj_count.min(self.verlet_objects.len());
+        for i in 0..obj_count {
+            self.release_polygon(self.verlet_objects.shape[i]);
+        }
+        self.verlet_objects.remove_front(obj_count);
+        for _ in 0..obj_count {
+            self.soft_bodies.remove_object(0);
+            self.rigid_bodies.remove_object(0);
+            self.phase_changes.remove_object(0);
+        }
     }
 
     pub fn remove_pos(&mut self, pos: Vec2) {
         for i in (0..self.verlet_objects.len()).rev() {
-            if (self.verlet_objects[i].position_current - pos).len() < self.verlet_objects[i].radius {
+            if (self.verlet_objects.position_current[i] - pos).len() < self.verlet_objects.radius[i] {
                 self.remove(i);
             }
         }
@@ -126,29 +408,126 @@ impl Solver {
 
     pub fn clear(&mut self) {
         self.verlet_objects.clear();
+        self.soft_bodies.clear();
+        self.rigid_bodies.clear();
+        self.phase_changes.clear();
+        self.polygons.clear();
+        self.free_polygons.clear();
     }
 
     pub fn spawn(&mut self, pos: Vec2) {
-        self.push(VerletObject::new(pos, self.spawn_radius));
+        let color: Color = self.next_spawn_color();
+        let mut obj: VerletObject = VerletObject::new(pos, self.spawn_radius, color);
+        obj.charge = self.spawn_charge;
+        obj.material = self.spawn_material;
+        obj.temperature = self.ambient_temperature;
+        obj.heat_capacity = self.materials.list[obj.material as usize].specific_heat * verlet::mass(obj.rest_radius);
+        if self.phase_changes.enabled {
+            obj.phase = self.materials.list[obj.material as usize].phase_at(obj.temperature);
+        }
+        if self.spawn_charge_alternate && self.verlet_objects.len() % 2 == 1 {
+            obj.charge = -obj.charge;
+        }
+        obj.shape = self.next_spawn_shape();
+        if !obj.shape.is_circle() {
+            obj.angle = thread_rng().gen_range(0.0..std::f32::consts::TAU);
+            obj.angle_old = obj.angle;
+        }
+        self.push(obj);
+    }
+
+    pub fn next_spawn_shape(&mut self) -> Shape {
+        match self.spawn_shape {
+            ShapeKind::Circle => Shape::Circle,
+            ShapeKind::Capsule => Shape::capsule(self.spawn_shape_aspect),
+            ShapeKind::Box => Shape::rectangle(self.spawn_shape_aspect),
+            ShapeKind::Polygon => {
+                let polygon: ConvexPolygon = if self.spawn_polygon_random {
+                    ConvexPolygon::random(self.spawn_polygon_sides, &mut thread_rng())
+                } else {
+                    ConvexPolygon::regular(self.spawn_polygon_sides)
+                };
+                self.polygon_shape(polygon)
+            },
+        }
+    }
+
+    // shape pointing at the polygon, which takes a free slot of the list or is added to its end
+    pub fn polygon_shape(&mut self, polygon: ConvexPolygon) -> Shape {
+        let index: u32 = match self.free_polygons.pop() {
+            Some(index) => {
+                self.polygons[index as usize] = polygon;
+                index
+            },
+            None => {
+                self.polygons.push(polygon);
+                (self.polygons.len() - 1) as u32
+            },
+        };
+        Shape::Polygon {index}
+    }
+
+    // frees the polygon slot of a removed object
+    fn release_polygon(&mut self, shape: Shape) {
+        if let Shape::Polygon {index} = shape {
+            self.free_polygons.push(index);
+        }
+    }
+
+    pub fn next_spawn_color(&self) -> Color {
+        if self.spawn_color_random_hue {
+            let mut rng: ThreadRng = thread_rng();
+            let mut color: Color = hsl_to_rgb(rng.gen_range(0.0..1.0), 1.0, 0.5);
+            color.a = self.spawn_color.a;
+            color
+        } else {
+            self.spawn_color
+        }
+    }
+
+    pub fn recolor_all(&mut self) {
+        for i in 0..self.verlet_objects.len() {
+            self.verlet_objects.color[i] = self.next_spawn_color();
+        }
+    }
+
+    // hexagonally packed objects filling the rectangle, rows from the bottom up
+    pub fn spawn_block(&mut self, min: Vec2, max: Vec2) {
+        let spacing: f32 = self.spawn_radius * 2.0;
+        let row_height: f32 = spacing * 3f32.sqrt() * 0.5;
+        let mut y: f32 = max.y - self.spawn_radius;
+        let mut row: usize = 0;
+        while y >= min.y + self.spawn_radius {
+            let mut x: f32 = min.x + self.spawn_radius + if row % 2 == 1 {self.spawn_radius} else {0.0};
+            while x <= max.x - self.spawn_radius {
+                self.spawn(Vec2 {x, y});
+                x += spacing;
+            }
+            y -= row_height;
+            row += 1;
+        }
+    }
+
+    pub fn recharge_all(&mut self) {
+        for i in 0..self.verlet_objects.len() {
+            let alternate: bool = self.spawn_charge_alternate && i % 2 == 1;
+            self.verlet_objects.charge[i] = if alternate {-self.spawn_charge} else {self.spawn_charge};
+        }
     }
 
     pub fn spawn_count(&mut self, spawn_count: usize) {
         let mut rng: ThreadRng = thread_rng();
         for _ in 0..spawn_count {
             let pos: Vec2 = {
-                let mut pos = Vec2 {
-                    x: rng.gen_range(self.spawn_radius..screen_width()-self.spawn_radius),
-                    y: rng.gen_range(self.spawn_radius..screen_height()-self.spawn_radius),
-                };
+                let mut pos = self.random_spawn_position(&mut rng);
                 'unsafe_pos: for _ in 0..self.spawn_safety_iterations {
                     for i in 0..self.verlet_objects.len() {
-                        let obj = self.verlet_objects[i];
+                        let obj: VerletObject = self.verlet_objects.get(i);
                         if !((obj.position_current - pos).len() < (obj.radius + self.spawn_radius) * self.spawn_safety_radius_factor) {
                             break 'unsafe_pos;
                         }
                     }
-                    pos.x = rng.gen_range(self.spawn_radius..screen_width()-self.spawn_radius);
-                    pos.y = rng.gen_range(self.spawn_radius..screen_height()-self.spawn_radius);
+                    pos = self.random_spawn_position(&mut rng);
                 }
                 pos
             };
@@ -159,10 +538,24 @@ impl Solver {
         }
     }
     
-    pub fn stabilize(&mut self) {
-        for i in 0..self.verlet_objects.len() {
-            self.verlet_objects[i].position_old = self.verlet_objects[i].position_current;
+    // random position on screen, inside of the container if one is found within the safe spawn iterations
+    fn random_spawn_position(&self, rng: &mut ThreadRng) -> Vec2 {
+        let mut pos: Vec2 = Vec2::zero();
+        for _ in 0..self.spawn_safety_iterations.max(1) {
+            pos = Vec2 {
+                x: rng.gen_range(self.spawn_radius..self.world_size.x-self.spawn_radius),
+                y: rng.gen_range(self.spawn_radius..self.world_size.y-self.spawn_radius),
+            };
+            if self.container.distance(pos) + self.spawn_radius <= 0.0 {
+                break;
+            }
         }
+        pos
+    }
+
+    pub fn stabilize(&mut self) {
+        self.verlet_objects.position_old.copy_from_slice(&self.verlet_objects.position_current);
+        self.verlet_objects.angle_old.copy_from_slice(&self.verlet_objects.angle);
     }
 
     pub fn accelerate_all(&mut self, intensity: f32, direction: f32) {
@@ -170,11 +563,16 @@ impl Solver {
             x: direction.cos() * intensity,
             y: direction.sin() * intensity,
         };
-        for i in 0..self.verlet_objects.len() {
-            self.verlet_objects[i].accelerate(vec);
+        for acceleration in self.verlet_objects.acceleration.iter_mut() {
+            *acceleration += vec;
         }
     }
 
+    pub fn set_world_size(&mut self, world_size: Vec2) {
+        self.world_size = world_size;
+        self.container.world_size = world_size;
+    }
+
     pub fn update_with_substep(&mut self, dt: f32, substebs: usize) {
         let sub_dt: f32 = dt / substebs as f32;
         for _ in 0..substebs {
@@ -183,96 +581,996 @@ impl Solver {
     }
 
     pub fn update(&mut self, dt: f32) {
+        self.last_dt = dt;
+        self.time += dt;
+        self.container.update(self.time);
+        self.update_wrap_size();
         if self.verlet_objects.is_empty() {self.enforce_object_count()};
         if self.verlet_objects.is_empty() {return};
         self.apply_gravity();
+        self.apply_thermal_expansion();
         self.apply_constraint();
         self.remove_oob_objs();
+        self.solve_soft_bodies();
+        self.solve_rigid_bodies();
+        self.solve_bonds();
+        if self.fluid.enabled {
+            self.solve_fluid();
+        }
         self.solve_collisions();
+        if self.phase_changes.enabled {
+            self.update_phases();
+        }
         self.update_positions(dt);
         self.enforce_object_count();
     }
 
+    // every field is updated in its own loop over a plain array, which lets the compiler vectorize them
     pub fn update_positions(&mut self, dt: f32) {
-        for obj in self.verlet_objects.iter_mut() {
-            obj.update_position(dt);
-            // heatloss
-            obj.temperature *= 0f32.max(1.0 - (dt*self.heat_loss_factor));
+        let (ambient_temperature, convection): (f32, f32) = (self.ambient_temperature, self.convection);
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        if self.parallel {
+            let mut objects: VerletObjects = std::mem::replace(&mut self.verlet_objects, VerletObjects::new());
+            self.thread_pool().install(|| {
+                objects.position_current.par_iter_mut()
+                    .zip(objects.position_old.par_iter_mut())
+                    .zip(objects.acceleration.par_iter())
+                    .for_each(|((position_current, position_old), &acceleration)| {
+                        integrate(position_current, position_old, acceleration, dt);
+                    });
+                objects.angle.par_iter_mut()
+                    .zip(objects.angle_old.par_iter_mut())
+                    .for_each(|(angle, angle_old)| integrate_angle(angle, angle_old));
+                objects.temperature.par_iter_mut()
+                    .zip(objects.radius.par_iter())
+                    .zip(objects.heat_capacity.par_iter())
+                    .for_each(|((temperature, &radius), &heat_capacity)| {
+                        *temperature = thermal::convect(*temperature, ambient_temperature, radius, heat_capacity, convection, dt);
+                    });
+            });
+            objects.acceleration.fill(Vec2::zero());
+            self.verlet_objects = objects;
+            return;
+        }
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        for ((position_current, position_old), &acceleration) in objects.position_current.iter_mut()
+            .zip(objects.position_old.iter_mut())
+            .zip(objects.acceleration.iter())
+        {
+            integrate(position_current, position_old, acceleration, dt);
+        }
+        objects.acceleration.fill(Vec2::zero());
+        for (angle, angle_old) in objects.angle.iter_mut().zip(objects.angle_old.iter_mut()) {
+            integrate_angle(angle, angle_old);
+        }
+        // heat lost to the surroundings
+        for ((temperature, &radius), &heat_capacity) in objects.temperature.iter_mut()
+            .zip(objects.radius.iter())
+            .zip(objects.heat_capacity.iter())
+        {
+            *temperature = thermal::convect(*temperature, ambient_temperature, radius, heat_capacity, convection, dt);
         }
     }
 
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn thread_pool(&mut self) -> &rayon::ThreadPool {
+        let thread_count: usize = self.thread_count.max(1);
+        if !matches!(self.thread_pool, Some((count, _)) if count == thread_count) {
+            let pool: rayon::ThreadPool = rayon::ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+                .unwrap();
+            self.thread_pool = Some((thread_count, pool));
+        }
+        &self.thread_pool.as_ref().unwrap().1
+    }
+
     pub fn apply_gravity(&mut self) {
-        for obj in self.verlet_objects.iter_mut() {
-            obj.accelerate(self.gravity);
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        if self.gravity_zones.is_empty() {
+            for acceleration in objects.acceleration.iter_mut() {
+                *acceleration += self.gravity;
+            }
             if self.accelerate_on_temperature {
-                obj.accelerate(self.gravity * -0f32.max((obj.temperature+1.0).powf(self.temperature_acceleration_power) - 1.0));
+                for (acceleration, &temperature) in objects.acceleration.iter_mut().zip(objects.temperature.iter()) {
+                    *acceleration += self.gravity * -0f32.max((temperature+1.0).powf(self.temperature_acceleration_power) - 1.0);
+                }
+            }
+            if self.phase_changes.enabled {
+                for (acceleration, &phase) in objects.acceleration.iter_mut().zip(objects.phase.iter()) {
+                    if phase == Phase::Gas {
+                        *acceleration += self.gravity * (self.phase_changes.gas_gravity - 1.0);
+                    }
+                }
+            }
+        } else {
+            for i in 0..objects.len() {
+                // the first zone containing the object replaces the global gravity
+                let gravity: Vec2 = self.gravity_zones.iter()
+                    .find(|zone| zone.contains(objects.position_current[i]))
+                    .map_or(self.gravity, |zone| zone.gravity);
+                objects.acceleration[i] += gravity;
+                if self.accelerate_on_temperature {
+                    objects.acceleration[i] += gravity * -0f32.max((objects.temperature[i]+1.0).powf(self.temperature_acceleration_power) - 1.0);
+                }
+                if self.phase_changes.enabled && objects.phase[i] == Phase::Gas {
+                    objects.acceleration[i] += gravity * (self.phase_changes.gas_gravity - 1.0);
+                }
+            }
+        }
+
+        // radial gravity
+        for well in &self.gravity_wells {
+            for (acceleration, &position) in objects.acceleration.iter_mut().zip(objects.position_current.iter()) {
+                *acceleration += well.acceleration(position);
+            }
+        }
+
+        if self.n_body.enabled {
+            self.apply_n_body();
+        }
+
+        if self.electrostatics.enabled {
+            self.apply_electrostatics();
+        }
+
+        if self.materials.cohesion_enabled {
+            self.apply_cohesion();
+        }
+
+        if self.materials.adhesion_enabled {
+            self.apply_adhesion();
+        }
+
+        self.apply_soft_body_pressure();
+    }
+
+    // positions of the given objects with every one moved next to the previous one across
+    // wrapped edges, so that rings lying across a seam stay in one piece
+    pub fn linked_positions(&self, indices: &[usize]) -> Vec<Vec2> {
+        let mut positions: Vec<Vec2> = Vec::with_capacity(indices.len());
+        for &i in indices {
+            let position: Vec2 = self.verlet_objects.position_current[i];
+            positions.push(match positions.last() {
+                Some(&previous) => self.nearest_image(previous, position),
+                None => position,
+            });
+        }
+        positions
+    }
+
+    pub fn spawn_soft_body(&mut self, center: Vec2) {
+        let ring_radius: f32 = self.soft_bodies.spawn_radius;
+        // members just touching each other around the ring
+        let count: usize = ((std::f32::consts::TAU * ring_radius / (self.spawn_radius * 2.0)) as usize).max(3);
+        let color: Color = self.next_spawn_color();
+        let first: usize = self.verlet_objects.len();
+        for i in 0..count {
+            let angle: f32 = std::f32::consts::TAU * i as f32 / count as f32;
+            self.spawn(center + Vec2 {x: angle.cos(), y: angle.sin()} * ring_radius);
+            self.verlet_objects.color[first + i] = color;
+        }
+        let indices: Vec<usize> = (first..first + count).collect();
+        let positions: Vec<Vec2> = self.linked_positions(&indices);
+        let rest_lengths: Vec<f32> = (0..count).map(|i| (positions[(i + 1) % count] - positions[i]).len()).collect();
+        self.soft_bodies.bodies.push(SoftBody {
+            indices,
+            rest_lengths,
+            rest_area: SoftBody::signed_area(&positions).abs(),
+            stiffness: self.soft_bodies.spawn_stiffness,
+            pressure: self.soft_bodies.spawn_pressure,
+            color,
+        });
+    }
+
+    pub fn spawn_rigid_body(&mut self, center: Vec2) {
+        let offsets: Vec<Vec2> = self.rigid_bodies.spawn_shape.offsets(self.rigid_bodies.spawn_size, self.spawn_radius * 2.0);
+        let middle: Vec2 = offsets.iter().fold(Vec2::zero(), |sum, &offset| sum + offset) / offsets.len() as f32;
+        let color: Color = self.next_spawn_color();
+        let first: usize = self.verlet_objects.len();
+        for (i, &offset) in offsets.iter().enumerate() {
+            self.spawn(center + offset - middle);
+            self.verlet_objects.color[first + i] = color;
+        }
+        self.rigid_bodies.bodies.push(RigidBody {
+            indices: (first..first + offsets.len()).collect(),
+            rest_offsets: offsets,
+            stiffness: self.rigid_bodies.spawn_stiffness,
+        });
+    }
+
+    // shape matching, members move toward the rigid transform of the rest shape that fits them best
+    pub fn solve_rigid_bodies(&mut self) {
+        for body in &self.rigid_bodies.bodies {
+            let positions: Vec<Vec2> = self.linked_positions(&body.indices);
+            let masses: Vec<f32> = body.indices.iter().map(|&i| verlet::mass(self.verlet_objects.rest_radius[i])).collect();
+            for ((&i, goal), &position) in body.indices.iter().zip(body.goals(&positions, &masses)).zip(positions.iter()) {
+                self.verlet_objects.position_current[i] += (goal - position) * body.stiffness;
             }
         }
     }
 
+    // springs between bonded frozen objects, solved like the soft body edges
+    pub fn solve_bonds(&mut self) {
+        let stiffness: f32 = self.phase_changes.bond_stiffness;
+        for bond in &self.phase_changes.bonds {
+            let position_1: Vec2 = self.verlet_objects.position_current[bond.i];
+            let offset: Vec2 = self.nearest_image(position_1, self.verlet_objects.position_current[bond.j]) - position_1;
+            let distance: f32 = offset.len();
+            if distance <= f32::EPSILON {
+                continue;
+            }
+            let correction: Vec2 = offset * ((distance - bond.rest_length) / distance * 0.5 * stiffness);
+            self.verlet_objects.position_current[bond.i] += correction;
+            self.verlet_objects.position_current[bond.j] -= correction;
+        }
+    }
+
+    // moves objects between phases, see phase::transition, objects that melt lose their bonds
+    pub fn update_phases(&mut self) {
+        let mut melted: Vec<bool> = vec![false; self.verlet_objects.len()];
+        let mut any_melted: bool = false;
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        for (i, melted) in melted.iter_mut().enumerate() {
+            let material: Material = self.materials.list[objects.material[i] as usize];
+            if !material.changes_phase {
+                continue;
+            }
+            let latent_heat: f32 = self.phase_changes.latent_heat(&material, objects.rest_radius[i]);
+            let phase_before: Phase = objects.phase[i];
+            phase::transition(
+                &material,
+                latent_heat,
+                objects.heat_capacity[i],
+                &mut objects.phase[i],
+                &mut objects.temperature[i],
+                &mut objects.phase_heat[i],
+            );
+            if objects.phase[i] != phase_before {
+                objects.bond_checked[i] = false;
+            }
+            if phase_before == Phase::Solid && objects.phase[i] != Phase::Solid {
+                *melted = true;
+                any_melted = true;
+            }
+        }
+        if any_melted {
+            self.phase_changes.bonds.retain(|bond| !melted[bond.i] && !melted[bond.j]);
+        }
+        self.bond_solids();
+    }
+
+    // bonds every solid object that hasn't looked for neighbours since its last phase change to the
+    // solid objects of its material touching it, which also picks up objects spawned frozen
+    pub fn bond_solids(&mut self) {
+        let objects: &VerletObjects = &self.verlet_objects;
+        let solid: Vec<bool> = objects.phase.iter().map(|&phase| phase == Phase::Solid).collect();
+        let unchecked: Vec<bool> = solid.iter()
+            .zip(objects.bond_checked.iter())
+            .map(|(&solid, &checked)| solid && !checked)
+            .collect();
+        if !unchecked.contains(&true) {
+            return;
+        }
+        // bonds the unchecked objects already have, which mustn't be added twice
+        let existing: HashSet<(usize, usize)> = self.phase_changes.bonds.iter()
+            .filter(|bond| unchecked[bond.i] || unchecked[bond.j])
+            .map(|bond| (bond.i.min(bond.j), bond.i.max(bond.j)))
+            .collect();
+        let max_radius: f32 = objects.radius.iter()
+            .zip(solid.iter())
+            .filter(|(_, &solid)| solid)
+            .fold(0.0, |max, (&radius, _)| max.max(radius));
+
+        self.build_force_pairs(|i| solid[i], max_radius * 2.0 * phase::BOND_REACH);
+        let objects: &VerletObjects = &self.verlet_objects;
+        let mut bonds: Vec<Bond> = vec![];
+        self.for_each_force_pair(|i, j| {
+            if !unchecked[i] && !unchecked[j] || objects.material[i] != objects.material[j] || existing.contains(&(i.min(j), i.max(j))) {
+                return;
+            }
+            let position_1: Vec2 = objects.position_current[i];
+            let distance: f32 = (self.nearest_image(position_1, objects.position_current[j]) - position_1).len();
+            let contact_distance: f32 = objects.radius[i] + objects.radius[j];
+            if distance < contact_distance * phase::BOND_REACH {
+                bonds.push(Bond {i, j, rest_length: distance.max(contact_distance)});
+            }
+        });
+        self.phase_changes.bonds.extend(bonds);
+        for (checked, unchecked) in self.verlet_objects.bond_checked.iter_mut().zip(unchecked) {
+            *checked |= unchecked;
+        }
+    }
+
+    // the gas inside every soft body pushes its edges outward, harder the more it's squeezed
+    pub fn apply_soft_body_pressure(&mut self) {
+        for body in &self.soft_bodies.bodies {
+            let positions: Vec<Vec2> = self.linked_positions(&body.indices);
+            let signed_area: f32 = SoftBody::signed_area(&positions);
+            let pressure: f32 = body.pressure * body.rest_area / signed_area.abs().max(1.0);
+            let count: usize = positions.len();
+            for member in 0..count {
+                let next: usize = (member + 1) % count;
+                let edge: Vec2 = positions[next] - positions[member];
+                // edge normal pointing out of the ring, with the length of the edge
+                let outward: Vec2 = if signed_area > 0.0 {
+                    Vec2 {x: edge.y, y: -edge.x}
+                } else {
+                    Vec2 {x: -edge.y, y: edge.x}
+                };
+                let force: Vec2 = outward * (pressure * 0.5);
+                for i in [body.indices[member], body.indices[next]] {
+                    self.verlet_objects.acceleration[i] += force / verlet::mass(self.verlet_objects.rest_radius[i]);
+                }
+            }
+        }
+    }
+
+    // springs along the edges of every soft body, solved on positions like the collisions
+    pub fn solve_soft_bodies(&mut self) {
+        for body in &self.soft_bodies.bodies {
+            let count: usize = body.indices.len();
+            for member in 0..count {
+                let (i, j) = (body.indices[member], body.indices[(member + 1) % count]);
+                let position_1: Vec2 = self.verlet_objects.position_current[i];
+                let offset: Vec2 = self.nearest_image(position_1, self.verlet_objects.position_current[j]) - position_1;
+                let distance: f32 = offset.len();
+                if distance <= f32::EPSILON {
+                    continue;
+                }
+                let correction: Vec2 = offset * ((distance - body.rest_lengths[member]) / distance * 0.5 * body.stiffness);
+                self.verlet_objects.position_current[i] += correction;
+                self.verlet_objects.position_current[j] -= correction;
+            }
+        }
+    }
+
+    pub fn apply_cohesion(&mut self) {
+        let max_radius: f32 = self.verlet_objects.radius.iter().fold(0.0, |max, &radius| max.max(radius));
+        let cutoff: f32 = max_radius * 2.0 * (1.0 + self.materials.band);
+        let materials: Materials = self.materials;
+        self.apply_pair_force(cutoff, |objects, i, j, offset| {
+            let strength: f32 = materials.cohesion[objects.material[i] as usize][objects.material[j] as usize];
+            let distance: f32 = offset.len();
+            if strength == 0.0 || distance <= f32::EPSILON {
+                return Vec2::zero();
+            }
+            let contact_distance: f32 = objects.radius[i] + objects.radius[j];
+            let profile: f32 = materials.band_profile(distance - contact_distance, contact_distance);
+            // strength is an acceleration, the force scales with the reduced mass of the pair
+            let (mass_1, mass_2) = (verlet::mass(objects.rest_radius[i]), verlet::mass(objects.rest_radius[j]));
+            let reduced_mass: f32 = mass_1 * mass_2 / (mass_1 + mass_2);
+            offset * (strength * profile * reduced_mass / distance)
+        });
+    }
+
+    // pulls objects just out of contact with a wall toward it
+    pub fn apply_adhesion(&mut self) {
+        let window_container: bool = self.container.is_window();
+        let (width, height): (f32, f32) = (self.world_size.x, self.world_size.y);
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        for i in 0..objects.len() {
+            let adhesion: f32 = self.materials.list[objects.material[i] as usize].wall_adhesion;
+            if adhesion == 0.0 {
+                continue;
+            }
+            let radius: f32 = objects.radius[i];
+            let position: Vec2 = objects.position_current[i];
+            let contact_distance: f32 = radius * 2.0;
+            if window_container {
+                // gap to every enabled side with its outward normal
+                let sides: [(bool, f32, Vec2); 4] = [
+                    (!self.wrap_y && self.apply_constraint_top, position.y - radius, Vec2 {x: 0.0, y: -1.0}),
+                    (!self.wrap_y && self.apply_constraint_bottom, height - radius - position.y, Vec2 {x: 0.0, y: 1.0}),
+                    (!self.wrap_x && self.apply_constraint_left, position.x - radius, Vec2 {x: -1.0, y: 0.0}),
+                    (!self.wrap_x && self.apply_constraint_right, width - radius - position.x, Vec2 {x: 1.0, y: 0.0}),
+                ];
+                for (enabled, gap, normal) in sides {
+                    if enabled {
+                        objects.acceleration[i] += normal * (adhesion * self.materials.band_profile(gap, contact_distance));
+                    }
+                }
+            } else {
+                let gap: f32 = -self.container.distance(position) - radius;
+                let profile: f32 = self.materials.band_profile(gap, contact_distance);
+                if profile > 0.0 {
+                    objects.acceleration[i] += self.container.normal(position) * (adhesion * profile);
+                }
+            }
+        }
+    }
+
+    pub fn apply_electrostatics(&mut self) {
+        let field: Vec2 = self.electrostatics.electric_field;
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        for ((acceleration, &charge), &rest_radius) in objects.acceleration.iter_mut()
+            .zip(objects.charge.iter())
+            .zip(objects.rest_radius.iter())
+        {
+            *acceleration += field * (charge / verlet::mass(rest_radius));
+        }
+
+        let electrostatics: Electrostatics = self.electrostatics;
+        self.apply_pair_force(electrostatics.cutoff, |objects, i, j, offset| {
+            electrostatics.force(objects.charge[i], objects.charge[j], offset)
+        });
+    }
+
+    // generic path for forces between pairs of objects closer than the cutoff, the returned
+    // force acts on the first object, the second one gets it negated, offset points from the
+    // first object to the nearest image of the second one, objects accelerate by force / mass
+    pub fn apply_pair_force(&mut self, cutoff: f32, force: impl Fn(&VerletObjects, usize, usize, Vec2) -> Vec2) {
+        if cutoff <= 0.0 {
+            return;
+        }
+        self.update_wrap_size();
+        self.build_force_pairs(|_| true, cutoff);
+
+        let objects: &VerletObjects = &self.verlet_objects;
+        let mut forces: Vec<Vec2> = vec![Vec2::zero(); objects.len()];
+        self.for_each_force_pair(|i, j| {
+            let position_2: Vec2 = self.nearest_image(objects.position_current[i], objects.position_current[j]);
+            let pair_force: Vec2 = force(objects, i, j, position_2 - objects.position_current[i]);
+            forces[i] += pair_force;
+            forces[j] -= pair_force;
+        });
+
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        for ((acceleration, pair_force), &rest_radius) in objects.acceleration.iter_mut()
+            .zip(forces)
+            .zip(objects.rest_radius.iter())
+        {
+            *acceleration += pair_force * (1.0 / verlet::mass(rest_radius));
+        }
+    }
+
+    // buckets the included objects for for_each_force_pair, with the spatial hash broadphase
+    // objects off screen interact as well, otherwise only those inside the window do
+    fn build_force_pairs(&mut self, include: impl Fn(usize) -> bool, cell_size: f32) {
+        let positions: &[Vec2] = &self.verlet_objects.position_current;
+        if self.broadphase == Broadphase::SpatialHash {
+            self.force_hash.wrap_x = self.wrap_x;
+            self.force_hash.wrap_y = self.wrap_y;
+            self.force_hash.build_subset(positions, include, cell_size, self.world_size.x, self.world_size.y);
+        } else {
+            self.force_grid.wrap_x = self.wrap_x;
+            self.force_grid.wrap_y = self.wrap_y;
+            self.force_grid.build_subset(positions, include, cell_size, self.world_size.x, self.world_size.y);
+        }
+    }
+
+    // every pair of objects in neighbouring cells of the last build_force_pairs, once
+    fn for_each_force_pair(&self, solve: impl FnMut(usize, usize)) {
+        if self.broadphase == Broadphase::SpatialHash {
+            self.force_hash.for_each_pair(solve);
+        } else {
+            self.force_grid.for_each_pair(solve);
+        }
+    }
+
+    pub fn apply_n_body(&mut self) {
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        if self.parallel {
+            self.thread_pool();
+            let (objects, n_body) = (&self.verlet_objects, &mut self.n_body);
+            let pool: &rayon::ThreadPool = &self.thread_pool.as_ref().unwrap().1;
+            let accelerations: Vec<Vec2> = pool.install(|| n_body.par_accelerations(&objects.position_current, &objects.rest_radius));
+            self.add_n_body_accelerations(accelerations);
+            return;
+        }
+        let objects: &VerletObjects = &self.verlet_objects;
+        let accelerations: Vec<Vec2> = self.n_body.accelerations(&objects.position_current, &objects.rest_radius);
+        self.add_n_body_accelerations(accelerations);
+    }
+
+    fn add_n_body_accelerations(&mut self, accelerations: Vec<Vec2>) {
+        let gravitational_constant: f32 = self.n_body.gravitational_constant;
+        for (acceleration, n_body_acceleration) in self.verlet_objects.acceleration.iter_mut().zip(accelerations) {
+            *acceleration += n_body_acceleration * gravitational_constant;
+        }
+    }
+
+    // objects of materials with thermal expansion take the radius of their temperature, keeping their mass
+    pub fn apply_thermal_expansion(&mut self) {
+        let coefficients: [f32; MATERIAL_COUNT] = self.materials.list.map(|material| material.thermal_expansion);
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        for (((radius, &rest_radius), &temperature), &material) in objects.radius.iter_mut()
+            .zip(objects.rest_radius.iter())
+            .zip(objects.temperature.iter())
+            .zip(objects.material.iter())
+        {
+            *radius = rest_radius * thermal::expansion(coefficients[material as usize], temperature);
+        }
+    }
+
     pub fn apply_constraint(&mut self) {
-        // value for optimizing cell size for next update
+        // value for optimizing cell size for next update, radii can change every substep
+        // through thermal expansion so the largest one is found again every time
         let mut max_radius: f32 = 1.0;
+        let window_container: bool = self.container.is_window();
         for i in (0..self.verlet_objects.len()).rev() {
             // previous iteration on temperature added the following formula
-            // (-((self.verlet_objects[i].position_current.x / screen_width()) - 0.5).abs() + 0.5) * 4.0;
+            // (-((self.verlet_objects.position_current[i].x / self.world_size.x) - 0.5).abs() + 0.5) * 4.0;
+            let radius: f32 = self.verlet_objects.radius[i];
+            let mut position_current: Vec2 = self.verlet_objects.position_current[i];
+            let mut position_old: Vec2 = self.verlet_objects.position_old[i];
+            let mut temperature: f32 = self.verlet_objects.temperature[i];
+            let heat_capacity: f32 = self.verlet_objects.heat_capacity[i];
+            let wall_conductance: f32 = self.conductivity * radius;
 
-            // top
-            if self.apply_constraint_top && self.verlet_objects[i].position_current.y < self.verlet_objects[i].radius {
-                self.verlet_objects[i].position_current.y = self.verlet_objects[i].radius;
-                if self.apply_bounce_top {
-                    self.verlet_objects[i].position_old.y += (self.verlet_objects[i].position_current.y - self.verlet_objects[i].position_old.y) * 2.0;
-                }
-                self.verlet_objects[i].temperature += self.apply_temperature_top;
+            // wrap around, the old position moves along to keep the velocity
+            if self.wrap_x {
+                let shift: f32 = position_current.x.div_euclid(self.world_size.x) * self.world_size.x;
+                position_current.x -= shift;
+                position_old.x -= shift;
             }
-            // bottom
-            if self.apply_constraint_bottom && self.verlet_objects[i].position_current.y > screen_height() - self.verlet_objects[i].radius {
-                self.verlet_objects[i].position_current.y = screen_height() - self.verlet_objects[i].radius;
-                if self.apply_bounce_bottom {
-                    self.verlet_objects[i].position_old.y += (self.verlet_objects[i].position_current.y - self.verlet_objects[i].position_old.y) * 2.0;
-                }
-                self.verlet_objects[i].temperature += self.apply_temperature_bottom;
+            if self.wrap_y {
+                let shift: f32 = position_current.y.div_euclid(self.world_size.y) * self.world_size.y;
+                position_current.y -= shift;
+                position_old.y -= shift;
             }
-            // left
-            if self.apply_constraint_left && self.verlet_objects[i].position_current.x < self.verlet_objects[i].radius {
-                self.verlet_objects[i].position_current.x = self.verlet_objects[i].radius;
-                if self.apply_bounce_left {
-                    self.verlet_objects[i].position_old.x += (self.verlet_objects[i].position_current.x - self.verlet_objects[i].position_old.x) * 2.0;
+
+            // circles only turn through friction, without it they take the simpler path
+            let turning: bool = !self.verlet_objects.shape[i].is_circle()
+                || self.friction > 0.0
+                || !window_container && self.container.wall_friction > 0.0;
+            if !turning {
+                // top
+                if window_container && !self.wrap_y && self.apply_constraint_top && position_current.y < radius {
+                    position_current.y = radius;
+                    if self.apply_bounce_top {
+                        position_old.y += (position_current.y - position_old.y) * 2.0;
+                    }
+                    temperature = self.wall_heat_top.warmed(temperature, heat_capacity, wall_conductance, self.last_dt);
                 }
-                self.verlet_objects[i].temperature += self.apply_temperature_left;
-            }
-            // right
-            if self.apply_constraint_right && self.verlet_objects[i].position_current.x > screen_width() - self.verlet_objects[i].radius {
-                self.verlet_objects[i].position_current.x = screen_width() - self.verlet_objects[i].radius;
-                if self.apply_bounce_right {
-                    self.verlet_objects[i].position_old.x += (self.verlet_objects[i].position_current.x - self.verlet_objects[i].position_old.x) * 2.0;
+                // bottom
+                if window_container && !self.wrap_y && self.apply_constraint_bottom && position_current.y > self.world_size.y - radius {
+                    position_current.y = self.world_size.y - radius;
+                    if self.apply_bounce_bottom {
+                        position_old.y += (position_current.y - position_old.y) * 2.0;
+                    }
+                    temperature = self.wall_heat_bottom.warmed(temperature, heat_capacity, wall_conductance, self.last_dt);
+                }
+                // left
+                if window_container && !self.wrap_x && self.apply_constraint_left && position_current.x < radius {
+                    position_current.x = radius;
+                    if self.apply_bounce_left {
+                        position_old.x += (position_current.x - position_old.x) * 2.0;
+                    }
+                    temperature = self.wall_heat_left.warmed(temperature, heat_capacity, wall_conductance, self.last_dt);
                 }
-                self.verlet_objects[i].temperature += self.apply_temperature_right;
+                // right
+                if window_container && !self.wrap_x && self.apply_constraint_right && position_current.x > self.world_size.x - radius {
+                    position_current.x = self.world_size.x - radius;
+                    if self.apply_bounce_right {
+                        position_old.x += (position_current.x - position_old.x) * 2.0;
+                    }
+                    temperature = self.wall_heat_right.warmed(temperature, heat_capacity, wall_conductance, self.last_dt);
+                }
+
+                // any other container shape
+                if !window_container {
+                    let penetration: f32 = self.container.distance(position_current) + radius;
+                    if penetration > 0.0 {
+                        let normal: Vec2 = self.container.normal(position_current);
+                        position_current -= normal * penetration;
+
+                        // velocities here are displacements per substep, relative to the (moving) wall
+                        let wall_velocity: Vec2 = self.container.wall_velocity(position_current) * self.last_dt;
+                        let relative_velocity: Vec2 = position_current - position_old - wall_velocity;
+                        let outward_speed: f32 = relative_velocity.dot(normal);
+                        if self.container.bounce && outward_speed > 0.0 {
+                            // reflect the part of the velocity going into the wall
+                            position_old += normal * outward_speed * 2.0;
+                        }
+                        let sliding_velocity: Vec2 = relative_velocity - normal * outward_speed;
+                        position_old += sliding_velocity * self.container.wall_friction;
+
+                        temperature = self.container.wall_heat.warmed(temperature, heat_capacity, wall_conductance, self.last_dt);
+                    }
+                }
+            }
+
+            self.verlet_objects.position_current[i] = position_current;
+            self.verlet_objects.position_old[i] = position_old;
+            self.verlet_objects.temperature[i] = temperature;
+            if turning {
+                self.constrain_turning(i);
+                position_current = self.verlet_objects.position_current[i];
             }
 
             // obj still outside constraints, handle OOB
+            let margin: f32 = radius + self.kill_region_margin;
             if
-                self.verlet_objects[i].position_current.is_nan() ||
-                self.verlet_objects[i].position_current.y < -self.verlet_objects[i].radius ||
-                self.verlet_objects[i].position_current.y > screen_height() + self.verlet_objects[i].radius ||
-                self.verlet_objects[i].position_current.x < -self.verlet_objects[i].radius ||
-                self.verlet_objects[i].position_current.x > screen_width() + self.verlet_objects[i].radius
+                position_current.is_nan() ||
+                self.kill_oob_objects && (
+                    position_current.y < -margin ||
+                    position_current.y > self.world_size.y + margin ||
+                    position_current.x < -margin ||
+                    position_current.x > self.world_size.x + margin
+                )
             {
-                self.verlet_objects.remove(i);
+                self.remove(i);
                 if self.stabilize_on_oob {
                     self.stabilize();
                 }
             } else {
-                max_radius = max_radius.max(self.verlet_objects[i].radius);
+                max_radius = max_radius.max(radius);
             }
         }
         self.cell_size = max_radius * CELL_SIZE_RADIUS_FACTOR;
     }
 
+    // walls push on the deepest point of an object instead of on its center, which turns it, used for
+    // shapes other than circles and for circles once friction can turn them, the walls don't move so
+    // only the ratio of inertia to mass matters and the mass is taken as one, bounces reverse the
+    // velocity of the touching point rather than of the center so they don't add energy
+    fn constrain_turning(&mut self, i: usize) {
+        let shape: Shape = self.verlet_objects.shape[i];
+        let radius: f32 = self.verlet_objects.radius[i];
+        let inverse_inertia: f32 = 1.0 / (radius * radius * shape.inertia_factor(&self.polygons)).max(f32::EPSILON);
+        let friction: f32 = self.friction;
+        let heat_capacity: f32 = self.verlet_objects.heat_capacity[i];
+        let (wall_conductance, dt): (f32, f32) = (self.conductivity * radius, self.last_dt);
+        let objects: &mut VerletObjects = &mut self.verlet_objects;
+        let arm = |objects: &VerletObjects, point: Vec2| ContactArm {
+            offset: point - objects.position_current[i],
+            inverse_mass: 1.0,
+            inverse_inertia,
+        };
+        // velocity of the touching point relative to the wall
+        let point_velocity = |objects: &VerletObjects, point: Vec2, wall_velocity: Vec2| -> Vec2 {
+            let velocity: Vec2 = objects.position_current[i] - objects.position_old[i];
+            arm(objects, point).point_velocity(velocity, objects.angle[i] - objects.angle_old[i]) - wall_velocity
+        };
+        // moves the touching point by the distance along the direction, either as a position correction
+        // or only as a change of its velocity through the old position
+        let push = |objects: &mut VerletObjects, point: Vec2, direction: Vec2, distance: f32, velocity_only: bool| {
+            let (shift, _, turn, _): (Vec2, Vec2, f32, f32) = shape::correction(direction, distance, arm(objects, point), ContactArm::fixed());
+            if velocity_only {
+                objects.position_old[i] -= shift;
+                objects.angle_old[i] -= turn;
+            } else {
+                objects.position_current[i] += shift;
+                objects.angle[i] += turn;
+            }
+        };
+        // the part of the velocity of the touching point along the wall
+        let sliding = |velocity: Vec2, normal: Vec2| -> (Vec2, f32) {
+            let sliding: Vec2 = velocity - normal * velocity.dot(normal);
+            let length: f32 = sliding.len();
+            (if length > f32::EPSILON {-sliding / length} else {Vec2::zero()}, length)
+        };
+
+        if self.container.is_window() {
+            // inward normal, where the wall is along it, whether it applies, bounces and how it heats
+            let walls: [(Vec2, f32, bool, bool, WallHeat); 4] = [
+                (Vec2 {x: 0.0, y: 1.0}, 0.0, !self.wrap_y && self.apply_constraint_top, self.apply_bounce_top, self.wall_heat_top),
+                (Vec2 {x: 0.0, y: -1.0}, -self.world_size.y, !self.wrap_y && self.apply_constraint_bottom, self.apply_bounce_bottom, self.wall_heat_bottom),
+                (Vec2 {x: 1.0, y: 0.0}, 0.0, !self.wrap_x && self.apply_constraint_left, self.apply_bounce_left, self.wall_heat_left),
+                (Vec2 {x: -1.0, y: 0.0}, -self.world_size.x, !self.wrap_x && self.apply_constraint_right, self.apply_bounce_right, self.wall_heat_right),
+            ];
+            for (normal, offset, enabled, bounce, wall_heat) in walls {
+                if !enabled {
+                    continue;
+                }
+                let hull: Hull = shape.hull(objects.position_current[i], objects.angle[i], radius, &self.polygons);
+                let (vertex, depth): (Vec2, f32) = hull.furthest(|vertex| offset - vertex.dot(normal));
+                if depth > 0.0 {
+                    let point: Vec2 = vertex - normal * hull.rounding;
+                    push(objects, point, normal, depth, false);
+                    // reflect the part of the velocity going into the wall
+                    let inward_speed: f32 = point_velocity(objects, point, Vec2::zero()).dot(normal);
+                    if bounce && inward_speed < 0.0 {
+                        push(objects, point, normal, -2.0 * inward_speed, true);
+                    }
+                    // coulomb friction, like between objects
+                    let (direction, length): (Vec2, f32) = sliding(point_velocity(objects, point, Vec2::zero()), normal);
+                    if friction > 0.0 {
+                        push(objects, point, direction, length.min(friction * depth), false);
+                    }
+                    objects.temperature[i] = wall_heat.warmed(objects.temperature[i], heat_capacity, wall_conductance, dt);
+                }
+            }
+        } else {
+            let hull: Hull = shape.hull(objects.position_current[i], objects.angle[i], radius, &self.polygons);
+            let (vertex, penetration): (Vec2, f32) = hull.furthest(|vertex| self.container.distance(vertex));
+            if penetration > 0.0 {
+                let outward: Vec2 = self.container.normal(vertex);
+                let point: Vec2 = vertex + outward * hull.rounding;
+                push(objects, point, -outward, penetration, false);
+
+                // same as for circles, velocities are displacements per substep relative to the (moving) wall
+                let wall_velocity: Vec2 = self.container.wall_velocity(point) * self.last_dt;
+                let outward_speed: f32 = point_velocity(objects, point, wall_velocity).dot(outward);
+                if self.container.bounce && outward_speed > 0.0 {
+                    push(objects, point, -outward, 2.0 * outward_speed, true);
+                }
+                let (direction, length): (Vec2, f32) = sliding(point_velocity(objects, point, wall_velocity), outward);
+                push(objects, point, direction, length * self.container.wall_friction, true);
+
+                objects.temperature[i] = self.container.wall_heat.warmed(objects.temperature[i], heat_capacity, wall_conductance, dt);
+            }
+        }
+    }
+
+    // kinetic energy of the linear and of the rotational motion, from the velocities of the last substep
+    pub fn kinetic_energy(&self) -> (f32, f32) {
+        let objects: &VerletObjects = &self.verlet_objects;
+        let dt: f32 = self.last_dt.max(f32::EPSILON);
+        let (mut linear, mut rotational): (f32, f32) = (0.0, 0.0);
+        for i in 0..objects.len() {
+            let mass: f32 = verlet::mass(objects.rest_radius[i]);
+            let inertia: f32 = mass * objects.radius[i] * objects.radius[i] * objects.shape[i].inertia_factor(&self.polygons);
+            let velocity: Vec2 = (objects.position_current[i] - objects.position_old[i]) / dt;
+            let angular_velocity: f32 = (objects.angle[i] - objects.angle_old[i]) / dt;
+            linear += 0.5 * mass * velocity.dot(velocity);
+            rotational += 0.5 * inertia * angular_velocity * angular_velocity;
+        }
+        (linear, rotational)
+    }
+
+    // spread of the top surface of the fluid objects, see fluid::surface_flatness
+    pub fn surface_flatness(&self) -> f32 {
+        let objects: &VerletObjects = &self.verlet_objects;
+        let positions = objects.position_current.iter()
+            .zip(objects.material.iter())
+            .filter(|(_, &material)| self.materials.list[material as usize].fluid)
+            .map(|(&position, _)| position);
+        fluid::surface_flatness(positions, self.spawn_radius * 4.0)
+    }
+
+    pub fn solve_fluid(&mut self) {
+        self.update_wrap_size();
+        let fluid_materials: [bool; MATERIAL_COUNT] = self.materials.list.map(|material| material.fluid);
+        let objects: &VerletObjects = &self.verlet_objects;
+        // frozen and boiled off objects of fluid materials aren't part of the liquid
+        let is_fluid: Vec<bool> = objects.material.iter()
+            .zip(objects.phase.iter())
+            .map(|(&material, &phase)| fluid_materials[material as usize] && phase == Phase::Liquid)
+            .collect();
+
+        // the largest fluid object sets the smoothing radius and the rest density
+        let radius: f32 = objects.radius.iter()
+            .zip(is_fluid.iter())
+            .filter(|(_, &fluid)| fluid)
+            .fold(0.0, |max, (&radius, _)| max.max(radius));
+        if radius <= 0.0 {
+            return;
+        }
+        let h: f32 = self.fluid.kernel_factor * radius * 2.0;
+        let rest_density: f32 = fluid::rest_density(radius * 2.0, h);
+        let self_density: f32 = fluid::poly6(0.0, h);
+        let relaxation: f32 = self.fluid.relaxation / (h * h);
+
+        self.build_force_pairs(|i| is_fluid[i], h);
+        let objects: &VerletObjects = &self.verlet_objects;
+        let mut pairs: Vec<(usize, usize)> = std::mem::take(&mut self.fluid.pairs);
+        pairs.clear();
+        self.for_each_force_pair(|i, j| {
+            let offset: Vec2 = objects.position_current[i] - self.nearest_image(objects.position_current[i], objects.position_current[j]);
+            if offset.x * offset.x + offset.y * offset.y < h * h {
+                pairs.push((i, j));
+            }
+        });
+
+        let count: usize = objects.len();
+        let mut densities: Vec<f32> = vec![0.0; count];
+        let mut gradient_sums: Vec<Vec2> = vec![Vec2::zero(); count];
+        let mut gradient_squares: Vec<f32> = vec![0.0; count];
+        let mut lambdas: Vec<f32> = vec![0.0; count];
+        let mut shifts: Vec<Vec2> = vec![Vec2::zero(); count];
+        let mut positions: Vec<Vec2> = objects.position_current.clone();
+        for _ in 0..self.fluid.iterations {
+            densities.fill(self_density);
+            gradient_sums.fill(Vec2::zero());
+            gradient_squares.fill(0.0);
+            for &(i, j) in &pairs {
+                let offset: Vec2 = positions[i] - self.nearest_image(positions[i], positions[j]);
+                let density: f32 = fluid::poly6(offset.x * offset.x + offset.y * offset.y, h);
+                densities[i] += density;
+                densities[j] += density;
+                let gradient: Vec2 = fluid::spiky_gradient(offset, h) * (1.0 / rest_density);
+                gradient_sums[i] += gradient;
+                gradient_sums[j] -= gradient;
+                let gradient_square: f32 = gradient.dot(gradient);
+                gradient_squares[i] += gradient_square;
+                gradient_squares[j] += gradient_square;
+            }
+
+            // scaling factor of every density constraint, objects below the rest density (at
+            // the surface) aren't pulled together, which would make them clump
+            for i in 0..count {
+                let constraint: f32 = (densities[i] / rest_density - 1.0).max(0.0);
+                lambdas[i] = -constraint / (gradient_sums[i].dot(gradient_sums[i]) + gradient_squares[i] + relaxation);
+            }
+
+            shifts.fill(Vec2::zero());
+            for &(i, j) in &pairs {
+                let offset: Vec2 = positions[i] - self.nearest_image(positions[i], positions[j]);
+                let shift: Vec2 = fluid::spiky_gradient(offset, h) * ((lambdas[i] + lambdas[j]) / rest_density);
+                shifts[i] += shift;
+                shifts[j] -= shift;
+            }
+            for i in 0..count {
+                if is_fluid[i] {
+                    positions[i] += shifts[i];
+                }
+            }
+        }
+
+        // xsph viscosity, velocities are the displacements since the last substep
+        let mut velocities: Vec<Vec2> = positions.iter().zip(objects.position_old.iter()).map(|(&current, &old)| current - old).collect();
+        if self.fluid.viscosity > 0.0 {
+            let mut velocity_shifts: Vec<Vec2> = vec![Vec2::zero(); count];
+            for &(i, j) in &pairs {
+                let offset: Vec2 = positions[i] - self.nearest_image(positions[i], positions[j]);
+                let weight: f32 = fluid::poly6(offset.x * offset.x + offset.y * offset.y, h) / rest_density * self.fluid.viscosity;
+                let difference: Vec2 = velocities[j] - velocities[i];
+                velocity_shifts[i] += difference * weight;
+                velocity_shifts[j] -= difference * weight;
+            }
+            for (velocity, velocity_shift) in velocities.iter_mut().zip(velocity_shifts) {
+                *velocity += velocity_shift;
+            }
+        }
+
+        for i in 0..count {
+            if is_fluid[i] {
+                self.verlet_objects.position_current[i] = positions[i];
+                self.verlet_objects.position_old[i] = positions[i] - velocities[i];
+            }
+        }
+        self.fluid.pairs = pairs;
+    }
+
     pub fn solve_collisions(&mut self) {
+        // reset collision diagnostics
+        self.pair_checks = 0;
+        self.verlet_objects.pressure.fill(0.0);
+        self.verlet_objects.contact_count.fill(0);
+
+        self.update_wrap_size();
+
+        match self.broadphase {
+            Broadphase::Flat => self.solve_collisions_flat_grid(),
+            Broadphase::MultiLevel => self.solve_collisions_multi_level_grid(),
+            Broadphase::SpatialHash => self.solve_collisions_spatial_hash(),
+            // the legacy grid doesn't know about seams
+            Broadphase::Nested if self.wrap_x || self.wrap_y => self.solve_collisions_flat_grid(),
+            Broadphase::Nested => self.solve_collisions_nested_grid(),
+        }
+    }
+
+    fn update_wrap_size(&mut self) {
+        self.wrap_size = Vec2 {
+            x: if self.wrap_x {self.world_size.x} else {0.0},
+            y: if self.wrap_y {self.world_size.y} else {0.0},
+        };
+    }
+
+    pub fn grid_size(&self) -> (usize, usize) {
+        match self.broadphase {
+            Broadphase::Flat => (self.grid.width, self.grid.height),
+            // finest level
+            Broadphase::MultiLevel => self.multi_level_grid.levels.first().map_or((0, 0), |grid| (grid.width, grid.height)),
+            // the hash has no dimensions, only a number of buckets
+            Broadphase::SpatialHash => (self.spatial_hash.bucket_count(), 1),
+            Broadphase::Nested => (self.cell_grid[0].len(), self.cell_grid.len()),
+        }
+    }
+
+    fn solve_collisions_flat_grid(&mut self) {
+        // the grid is moved out so its cells can be borrowed while objects are mutated
+        let mut grid: CellGrid = std::mem::replace(&mut self.grid, CellGrid::new());
+        grid.wrap_x = self.wrap_x;
+        grid.wrap_y = self.wrap_y;
+        grid.build(&self.verlet_objects.position_current, self.cell_size, self.world_size.x, self.world_size.y);
+        if self.sort_objects_by_cell {
+            let order: Vec<usize> = grid.reorder(&mut self.verlet_objects);
+            self.soft_bodies.permute(&order);
+            self.rigid_bodies.permute(&order);
+            self.phase_changes.permute(&order);
+        }
+
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        // stripes at both ends of a wrapped x axis are neighbours, so those are solved sequentially
+        if self.parallel && self.solve_pairs_once && !self.wrap_x {
+            self.solve_grid_parallel(&grid);
+            self.grid = grid;
+            return;
+        }
+
+        let mut objects: VerletObjects = std::mem::replace(&mut self.verlet_objects, VerletObjects::new());
+        let mut pair_checks: usize = 0;
+        let mut solve = |obj_index_1: usize, obj_index_2: usize| {
+            pair_checks += 1;
+            self.collide(&mut objects, obj_index_1, obj_index_2);
+        };
+        if self.solve_pairs_once {
+            grid.for_each_pair(&mut solve);
+        } else {
+            grid.for_each_pair_full(&mut solve);
+        }
+        self.verlet_objects = objects;
+        self.pair_checks = pair_checks;
+
+        self.grid = grid;
+    }
+
+    // column stripes are at least 2 cells wide, so stripes of the same parity never share
+    // objects and can be solved at the same time, the even ones first and then the odd ones,
+    // the stripe layout only depends on thread count which keeps the result deterministic
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn solve_grid_parallel(&mut self, grid: &CellGrid) {
+        let thread_count: usize = self.thread_count.max(1);
+        let stripe_width: usize = 2usize.max(grid.width.div_ceil(thread_count * 2));
+        let stripe_count: usize = grid.width.div_ceil(stripe_width);
+        let objects: SharedVerletObjects = self.verlet_objects.shared();
+
+        // the pool is moved out so the solver itself can be shared between threads
+        self.thread_pool();
+        let (pool_thread_count, pool) = self.thread_pool.take().unwrap();
+        let solver: &Solver = self;
+        let mut pair_checks: usize = 0;
+        for parity in 0..2 {
+            let stripes: Vec<usize> = (parity..stripe_count).step_by(2).collect();
+            pair_checks += pool.install(|| {
+                stripes.par_iter().map(|&stripe| {
+                    let mut stripe_pair_checks: usize = 0;
+                    let x_end: usize = grid.width.min((stripe + 1) * stripe_width);
+                    grid.for_each_pair_in_columns(stripe * stripe_width, x_end, |obj_index_1, obj_index_2| {
+                        stripe_pair_checks += 1;
+                        unsafe { solver.collide_shared(objects, obj_index_1, obj_index_2) };
+                    });
+                    stripe_pair_checks
+                }).sum::<usize>()
+            });
+        }
+        self.thread_pool = Some((pool_thread_count, pool));
+        self.pair_checks = pair_checks;
+    }
+
+    fn solve_collisions_multi_level_grid(&mut self) {
+        let mut grid: MultiLevelGrid = std::mem::replace(&mut self.multi_level_grid, MultiLevelGrid::new());
+        grid.wrap_x = self.wrap_x;
+        grid.wrap_y = self.wrap_y;
+        grid.build(&self.verlet_objects.position_current, &self.verlet_objects.radius, CELL_SIZE_RADIUS_FACTOR, self.world_size.x, self.world_size.y);
+
+        let mut objects: VerletObjects = std::mem::replace(&mut self.verlet_objects, VerletObjects::new());
+        let mut pair_checks: usize = 0;
+        grid.for_each_pair(|obj_index_1, obj_index_2| {
+            pair_checks += 1;
+            self.collide(&mut objects, obj_index_1, obj_index_2);
+        });
+        self.verlet_objects = objects;
+        self.pair_checks = pair_checks;
+
+        self.multi_level_grid = grid;
+    }
+
+    fn solve_collisions_spatial_hash(&mut self) {
+        let mut grid: SpatialHash = std::mem::replace(&mut self.spatial_hash, SpatialHash::new());
+        grid.wrap_x = self.wrap_x;
+        grid.wrap_y = self.wrap_y;
+        grid.build(&self.verlet_objects.position_current, self.cell_size, self.world_size.x, self.world_size.y);
+
+        let mut objects: VerletObjects = std::mem::replace(&mut self.verlet_objects, VerletObjects::new());
+        let mut pair_checks: usize = 0;
+        grid.for_each_pair(|obj_index_1, obj_index_2| {
+            pair_checks += 1;
+            self.collide(&mut objects, obj_index_1, obj_index_2);
+        });
+        self.verlet_objects = objects;
+        self.pair_checks = pair_checks;
+
+        self.spatial_hash = grid;
+    }
+
+    fn solve_collisions_nested_grid(&mut self) {
         // create cell grid
-        let grid_width: usize = (screen_width() / self.cell_size).ceil() as usize;
-        let grid_height: usize = (screen_height() / self.cell_size).ceil() as usize;
+        let grid_width: usize = (self.world_size.x / self.cell_size).ceil() as usize;
+        let grid_height: usize = (self.world_size.y / self.cell_size).ceil() as usize;
 
         if grid_height <= self.cell_grid.len() && grid_width <= self.cell_grid[0].len() {
             // only clear cells that are necessary
@@ -294,7 +1592,7 @@ impl Solver {
 
         // assign to cells
         for i in 0..self.verlet_objects.len() {
-            let Vec2{x, y} = self.verlet_objects[i].position_current;
+            let Vec2{x, y} = self.verlet_objects.position_current[i];
             let grid_x: usize = (x / self.cell_size).floor() as usize;
             let grid_y: usize = (y / self.cell_size).floor() as usize;
             if (0..grid_width).contains(&grid_x) && (0..grid_height).contains(&grid_y) {
@@ -337,18 +1635,182 @@ impl Solver {
     }
 
     pub fn solve_object_collision(&mut self, obj_index_1: usize, obj_index_2: usize) {
-        let collision_axis: Vec2 = self.verlet_objects[obj_index_1].position_current - self.verlet_objects[obj_index_2].position_current;
+        self.pair_checks += 1;
+        let mut objects: VerletObjects = std::mem::replace(&mut self.verlet_objects, VerletObjects::new());
+        self.collide(&mut objects, obj_index_1, obj_index_2);
+        self.verlet_objects = objects;
+    }
+
+    // narrow phase shared by the sequential and the parallel collision solving,
+    // only takes the fields it needs so it doesn't depend on how objects are stored,
+    // the overlap is split by inverse mass so the heavier object moves less
+    pub fn contact(&self, position_1: Vec2, radius_1: f32, mass_1: f32, position_2: Vec2, radius_2: f32, mass_2: f32) -> Option<Contact> {
+        let collision_axis: Vec2 = position_1 - position_2;
         let dist: f32 = collision_axis.len();
-        let radii: f32 = self.verlet_objects[obj_index_1].radius + self.verlet_objects[obj_index_2].radius;
+        let radii: f32 = radius_1 + radius_2;
         if dist < radii {
             let n: Vec2 = collision_axis / dist;
             let delta: f32 = radii - dist;
-            self.verlet_objects[obj_index_1].position_current += n * 0.5 * delta;
-            self.verlet_objects[obj_index_2].position_current -= n * 0.5 * delta;
+            let share_1: f32 = mass_2 / (mass_1 + mass_2);
+            Some(Contact {
+                normal: n,
+                point: position_2 + n * (radius_2 - delta * 0.5),
+                shift_1: n * share_1 * delta,
+                shift_2: n * (share_1 - 1.0) * delta,
+                turn_1: 0.0,
+                turn_2: 0.0,
+                overlap: delta,
+                heat: 0.0,
+            })
+        } else {
+            None
+        }
+    }
 
-            let tmp_diff = ((self.verlet_objects[obj_index_1].temperature - self.verlet_objects[obj_index_2].temperature) / 2.0) * self.heat_transfer_factor;
-            self.verlet_objects[obj_index_1].temperature -= tmp_diff;
-            self.verlet_objects[obj_index_2].temperature += tmp_diff;
+    // narrow phase of any pair, circles take the fast path
+    pub fn body_contact(&self, body_1: &ContactBody, body_2: &ContactBody) -> Option<Contact> {
+        let mut contact: Contact = if body_1.shape.is_circle() && body_2.shape.is_circle() {
+            self.contact(
+                body_1.position, body_1.radius, verlet::mass(body_1.rest_radius),
+                body_2.position, body_2.radius, verlet::mass(body_2.rest_radius),
+            )?
+        } else {
+            self.shape_contact(body_1, body_2)?
+        };
+        if self.friction > 0.0 {
+            self.add_friction(&mut contact, body_1, body_2);
+        }
+        // the conductance goes with the size of the contact rather than with the overlap, which
+        // shrinks with every added substep as objects get pushed apart more often
+        contact.heat = thermal::exchanged_heat(
+            body_1.temperature - body_2.temperature,
+            thermal::reduced_heat_capacity(body_1.heat_capacity, body_2.heat_capacity),
+            self.conductivity * body_1.radius.min(body_2.radius) * self.last_dt,
+        );
+        Some(contact)
+    }
+
+    // narrow phase for pairs that aren't both circles, see shape::hull_contact,
+    // heavier objects move less and contacts away from the center turn the objects
+    pub fn shape_contact(&self, body_1: &ContactBody, body_2: &ContactBody) -> Option<Contact> {
+        let hull_1: Hull = body_1.shape.hull(body_1.position, body_1.angle, body_1.radius, &self.polygons);
+        let hull_2: Hull = body_2.shape.hull(body_2.position, body_2.angle, body_2.radius, &self.polygons);
+        let contact: HullContact = shape::hull_contact(&hull_1, &hull_2)?;
+        let (shift_1, shift_2, turn_1, turn_2): (Vec2, Vec2, f32, f32) = shape::correction(
+            contact.normal,
+            contact.depth,
+            self.contact_arm(body_1, contact.point),
+            self.contact_arm(body_2, contact.point),
+        );
+        Some(Contact {
+            normal: contact.normal,
+            point: contact.point,
+            shift_1,
+            shift_2,
+            turn_1,
+            turn_2,
+            overlap: contact.depth,
+            heat: 0.0,
+        })
+    }
+
+    // coulomb friction, the sliding of the touching points against each other over the last substep
+    // is undone up to the friction times the overlap, which turns both objects so they start rolling
+    fn add_friction(&self, contact: &mut Contact, body_1: &ContactBody, body_2: &ContactBody) {
+        let arm_1: ContactArm = self.contact_arm(body_1, contact.point);
+        let arm_2: ContactArm = self.contact_arm(body_2, contact.point);
+        let velocity: Vec2 = arm_1.point_velocity(body_1.velocity, body_1.angular_velocity)
+            - arm_2.point_velocity(body_2.velocity, body_2.angular_velocity);
+        let sliding: Vec2 = velocity - contact.normal * velocity.dot(contact.normal);
+        let length: f32 = sliding.len();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let (shift_1, shift_2, turn_1, turn_2): (Vec2, Vec2, f32, f32) = shape::correction(
+            -sliding / length,
+            length.min(self.friction * contact.overlap),
+            arm_1,
+            arm_2,
+        );
+        contact.shift_1 += shift_1;
+        contact.shift_2 += shift_2;
+        contact.turn_1 += turn_1;
+        contact.turn_2 += turn_2;
+    }
+
+    fn contact_arm(&self, body: &ContactBody, point: Vec2) -> ContactArm {
+        let mass: f32 = verlet::mass(body.rest_radius);
+        ContactArm {
+            offset: point - body.position,
+            inverse_mass: 1.0 / mass,
+            inverse_inertia: 1.0 / (mass * body.radius * body.radius * body.shape.inertia_factor(&self.polygons)),
+        }
+    }
+
+    // position_2 moved by whole world sizes along the wrapped axes to be as close as possible to position_1
+    pub fn nearest_image(&self, position_1: Vec2, position_2: Vec2) -> Vec2 {
+        let mut position: Vec2 = position_2;
+        if self.wrap_size.x > 0.0 {
+            position.x += ((position_1.x - position.x) / self.wrap_size.x).round() * self.wrap_size.x;
+        }
+        if self.wrap_size.y > 0.0 {
+            position.y += ((position_1.y - position.y) / self.wrap_size.y).round() * self.wrap_size.y;
+        }
+        position
+    }
+
+    // the density constraint keeps liquid objects apart instead of the collisions,
+    // which would stack them like marbles, frozen or boiled off objects collide as usual
+    fn liquid_pair(&self, (material_1, phase_1): (u8, Phase), (material_2, phase_2): (u8, Phase)) -> bool {
+        self.fluid.enabled
+            && self.materials.list[material_1 as usize].fluid && phase_1 == Phase::Liquid
+            && self.materials.list[material_2 as usize].fluid && phase_2 == Phase::Liquid
+    }
+
+    pub fn collide(&self, objects: &mut VerletObjects, i: usize, j: usize) {
+        let position_2: Vec2 = self.nearest_image(objects.position_current[i], objects.position_current[j]);
+        let contact: Option<Contact> = self.body_contact(
+            &ContactBody::new(objects, i, objects.position_current[i]),
+            &ContactBody::new(objects, j, position_2),
+        );
+        if let Some(contact) = contact {
+            if !self.liquid_pair((objects.material[i], objects.phase[i]), (objects.material[j], objects.phase[j])) {
+                objects.position_current[i] += contact.shift_1;
+                objects.position_current[j] += contact.shift_2;
+                objects.angle[i] += contact.turn_1;
+                objects.angle[j] += contact.turn_2;
+            }
+            objects.pressure[i] += contact.overlap;
+            objects.pressure[j] += contact.overlap;
+            objects.contact_count[i] += 1;
+            objects.contact_count[j] += 1;
+            objects.temperature[i] -= contact.heat / objects.heat_capacity[i];
+            objects.temperature[j] += contact.heat / objects.heat_capacity[j];
+        }
+    }
+
+    // same as collide but through the raw view used by the collision solving threads
+    // safety: i and j have to differ and no other thread may access either object
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    unsafe fn collide_shared(&self, objects: SharedVerletObjects, i: usize, j: usize) {
+        let position_2: Vec2 = self.nearest_image(*objects.position_current.add(i), *objects.position_current.add(j));
+        let contact: Option<Contact> = self.body_contact(
+            &ContactBody::shared(objects, i, *objects.position_current.add(i)),
+            &ContactBody::shared(objects, j, position_2),
+        );
+        if let Some(contact) = contact {
+            if !self.liquid_pair((*objects.material.add(i), *objects.phase.add(i)), (*objects.material.add(j), *objects.phase.add(j))) {
+                *objects.position_current.add(i) += contact.shift_1;
+                *objects.position_current.add(j) += contact.shift_2;
+                *objects.angle.add(i) += contact.turn_1;
+                *objects.angle.add(j) += contact.turn_2;
+            }
+            *objects.pressure.add(i) += contact.overlap;
+            *objects.pressure.add(j) += contact.overlap;
+            *objects.contact_count.add(i) += 1;
+            *objects.contact_count.add(j) += 1;
+            *objects.temperature.add(i) -= contact.heat / *objects.heat_capacity.add(i);
+            *objects.temperature.add(j) += contact.heat / *objects.heat_capacity.add(j);
         }
     }
 
@@ -370,3 +1832,75 @@ impl Solver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // objects spawning and leaving all the time keep reusing the same polygon slots
+    #[test]
+    fn polygon_slots_are_reused() {
+        let mut solver: Solver = Solver::new(Vec2 {x: 400.0, y: 300.0});
+        solver.spawn_shape = ShapeKind::Polygon;
+        solver.spawn_polygon_random = true;
+        let mut most_objects: usize = 0;
+        for round in 0..50 {
+            for i in 0..20 {
+                solver.spawn(Vec2 {x: 20.0 + i as f32 * 15.0, y: 150.0});
+            }
+            most_objects = most_objects.max(solver.verlet_objects.len());
+            if round % 2 == 0 {
+                solver.remove_count(15);
+            } else {
+                for i in (0..solver.verlet_objects.len()).rev().step_by(3) {
+                    solver.remove(i);
+                }
+            }
+        }
+        assert_eq!(solver.polygons.len(), most_objects);
+
+        // every remaining object still owns a slot of its own
+        let mut indices: HashSet<u32> = HashSet::new();
+        for shape in &solver.verlet_objects.shape {
+            let Shape::Polygon {index} = *shape else {
+                panic!("spawned a shape other than a polygon");
+            };
+            assert!((index as usize) < solver.polygons.len());
+            assert!(indices.insert(index), "polygon slot {index} shared by two objects");
+        }
+    }
+
+    fn bond_pairs(solver: &Solver) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<(usize, usize)> = solver.phase_changes.bonds.iter().map(|bond| (bond.i.min(bond.j), bond.i.max(bond.j))).collect();
+        pairs.sort();
+        pairs
+    }
+
+    // objects only look for neighbours once per phase change, but then bond to bonded ones too
+    #[test]
+    fn solids_bond_once_per_phase_change() {
+        let mut solver: Solver = Solver::new(Vec2 {x: 400.0, y: 300.0});
+        solver.spawn_radius = 10.0;
+        for x in [100.0, 120.0, 140.0, 300.0] {
+            solver.spawn(Vec2 {x, y: 150.0});
+        }
+        solver.verlet_objects.phase.fill(Phase::Solid);
+        solver.bond_solids();
+        assert_eq!(bond_pairs(&solver), [(0, 1), (1, 2)]);
+        assert!(solver.verlet_objects.bond_checked.iter().all(|&checked| checked));
+
+        // the lone object touching the row later doesn't bond until it changes phase
+        solver.verlet_objects.position_current[3] = Vec2 {x: 160.0, y: 150.0};
+        solver.bond_solids();
+        assert_eq!(bond_pairs(&solver), [(0, 1), (1, 2)]);
+        solver.verlet_objects.bond_checked[3] = false;
+        solver.bond_solids();
+        assert_eq!(bond_pairs(&solver), [(0, 1), (1, 2), (2, 3)]);
+
+        // checking an object again doesn't duplicate the bonds it has
+        solver.verlet_objects.bond_checked[1] = false;
+        solver.bond_solids();
+        assert_eq!(bond_pairs(&solver), [(0, 1), (1, 2), (2, 3)]);
+    }
+}
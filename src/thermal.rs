@@ -0,0 +1,98 @@
+// heat an object touching a wall gains over a substep, depending on how the wall is set up
+#[derive(Clone, Copy, PartialEq)]
+pub enum WallHeatMode {
+    // no heat goes through the wall
+    Insulated,
+    // the wall is a heat bath at its temperature, touching objects are conducted toward it
+    FixedTemperature,
+    // every touching object gains a constant heat per second, negative cools
+    HeatFlux,
+}
+
+impl WallHeatMode {
+    pub const ALL: [Self; 3] = [
+        Self::Insulated,
+        Self::FixedTemperature,
+        Self::HeatFlux,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Insulated => "Insulated",
+            Self::FixedTemperature => "Fixed temperature",
+            Self::HeatFlux => "Heat flux",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct WallHeat {
+    pub mode: WallHeatMode,
+    // only used by fixed temperature walls
+    pub temperature: f32,
+    // heat per second per touching object, only used by heat flux walls
+    pub flux: f32,
+}
+
+impl WallHeat {
+    pub fn default() -> Self {
+        Self {
+            mode: WallHeatMode::Insulated,
+            temperature: 0.0,
+            flux: 0.0,
+        }
+    }
+
+    pub fn fixed_temperature(temperature: f32) -> Self {
+        Self {
+            mode: WallHeatMode::FixedTemperature,
+            temperature,
+            ..Self::default()
+        }
+    }
+
+    pub fn heat_flux(flux: f32) -> Self {
+        Self {
+            mode: WallHeatMode::HeatFlux,
+            flux,
+            ..Self::default()
+        }
+    }
+
+    // temperature of an object after touching the wall for dt, the wall itself never warms up
+    pub fn warmed(&self, temperature: f32, heat_capacity: f32, conductance: f32, dt: f32) -> f32 {
+        let heat: f32 = match self.mode {
+            WallHeatMode::Insulated => 0.0,
+            WallHeatMode::FixedTemperature => exchanged_heat(self.temperature - temperature, heat_capacity, conductance * dt),
+            WallHeatMode::HeatFlux => self.flux * dt,
+        };
+        temperature + heat / heat_capacity.max(f32::EPSILON)
+    }
+}
+
+// heat flowing down a temperature difference through a contact of the given conductance (heat per
+// second per degree) over dt, solved exactly for the two bodies on their own rather than stepped,
+// so it never overshoots the common temperature and splitting dt into more substeps changes nothing,
+// heat_capacity is the reduced capacity of the pair, c1 * c2 / (c1 + c2), or the object's own
+// capacity against a wall
+pub fn exchanged_heat(temperature_difference: f32, heat_capacity: f32, conductance_dt: f32) -> f32 {
+    let heat_capacity: f32 = heat_capacity.max(f32::EPSILON);
+    temperature_difference * heat_capacity * (1.0 - (-conductance_dt / heat_capacity).exp())
+}
+
+pub fn reduced_heat_capacity(heat_capacity_1: f32, heat_capacity_2: f32) -> f32 {
+    heat_capacity_1 * heat_capacity_2 / (heat_capacity_1 + heat_capacity_2).max(f32::EPSILON)
+}
+
+// radius over rest radius at the temperature, kept within a range so very hot objects don't blow up
+// the grid cells and very cold ones don't vanish
+pub fn expansion(coefficient: f32, temperature: f32) -> f32 {
+    (1.0 + coefficient * temperature).clamp(0.5, 2.0)
+}
+
+// temperature after losing heat to the surrounding air over dt, exact exponential relaxation toward it,
+// convection is heat per second per degree per unit of the object's circumference
+pub fn convect(temperature: f32, ambient_temperature: f32, radius: f32, heat_capacity: f32, convection: f32, dt: f32) -> f32 {
+    let conductance: f32 = convection * std::f32::consts::TAU * radius;
+    ambient_temperature + (temperature - ambient_temperature) * (-conductance * dt / heat_capacity.max(f32::EPSILON)).exp()
+}
@@ -0,0 +1,205 @@
+use crate::context::Context;
+
+// numeric rules the timeline can animate
+#[derive(Clone, Copy, PartialEq)]
+pub enum Parameter {
+    GravityX,
+    GravityY,
+    TemperatureAccelerationPower,
+    Conductivity,
+    Convection,
+    AmbientTemperature,
+    ShakeIntensity,
+    ShakeDirection,
+    ShakeRate,
+    SpawnRate,
+}
+
+impl Parameter {
+    pub const ALL: [Self; 10] = [
+        Self::GravityX,
+        Self::GravityY,
+        Self::TemperatureAccelerationPower,
+        Self::Conductivity,
+        Self::Convection,
+        Self::AmbientTemperature,
+        Self::ShakeIntensity,
+        Self::ShakeDirection,
+        Self::ShakeRate,
+        Self::SpawnRate,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::GravityX => "Gravity X",
+            Self::GravityY => "Gravity Y",
+            Self::TemperatureAccelerationPower => "Temperature acceleration factor",
+            Self::Conductivity => "Conductivity",
+            Self::Convection => "Convection",
+            Self::AmbientTemperature => "Ambient temperature",
+            Self::ShakeIntensity => "Shake intensity",
+            Self::ShakeDirection => "Shake direction",
+            Self::ShakeRate => "Shakes per second",
+            Self::SpawnRate => "Spawns per second",
+        }
+    }
+
+    pub fn get(&self, context: &Context) -> f32 {
+        match self {
+            Self::GravityX => context.solver.gravity.x,
+            Self::GravityY => context.solver.gravity.y,
+            Self::TemperatureAccelerationPower => context.solver.temperature_acceleration_power,
+            Self::Conductivity => context.solver.conductivity,
+            Self::Convection => context.solver.convection,
+            Self::AmbientTemperature => context.solver.ambient_temperature,
+            Self::ShakeIntensity => context.shake_intensity,
+            Self::ShakeDirection => context.shake_direction,
+            Self::ShakeRate => context.shake_rate,
+            Self::SpawnRate => context.spawn_rate,
+        }
+    }
+
+    pub fn set(&self, context: &mut Context, value: f32) {
+        match self {
+            Self::GravityX => context.solver.gravity.x = value,
+            Self::GravityY => context.solver.gravity.y = value,
+            Self::TemperatureAccelerationPower => context.solver.temperature_acceleration_power = value,
+            Self::Conductivity => context.solver.conductivity = value,
+            Self::Convection => context.solver.convection = value,
+            Self::AmbientTemperature => context.solver.ambient_temperature = value,
+            Self::ShakeIntensity => context.shake_intensity = value,
+            Self::ShakeDirection => context.shake_direction = value,
+            Self::ShakeRate => context.shake_rate = value,
+            Self::SpawnRate => context.spawn_rate = value,
+        }
+    }
+}
+
+// how the value gets from a keyframe to the next one
+#[derive(Clone, Copy, PartialEq)]
+pub enum Curve {
+    Step,
+    Linear,
+    Ease,
+}
+
+impl Curve {
+    pub const ALL: [Self; 3] = [
+        Self::Step,
+        Self::Linear,
+        Self::Ease,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Step => "Step",
+            Self::Linear => "Linear",
+            Self::Ease => "Ease",
+        }
+    }
+
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Self::Step => 0.0,
+            Self::Linear => t,
+            Self::Ease => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: f32,
+    pub curve: Curve,
+}
+
+pub struct Track {
+    pub parameter: Parameter,
+    pub enabled: bool,
+    // sorted by time
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    pub fn new(parameter: Parameter) -> Self {
+        Self {
+            parameter,
+            enabled: true,
+            keyframes: vec![],
+        }
+    }
+
+    pub fn add(&mut self, time: f32, value: f32, curve: Curve) {
+        self.keyframes.push(Keyframe {time, value, curve});
+        self.sort();
+    }
+
+    pub fn sort(&mut self) {
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    // holds the first and last values before and after the keyframes
+    pub fn value_at(&self, time: f32) -> Option<f32> {
+        let first: &Keyframe = self.keyframes.first()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        for pair in self.keyframes.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if time < to.time {
+                let t: f32 = (time - from.time) / (to.time - from.time);
+                return Some(from.value + (to.value - from.value) * from.curve.apply(t));
+            }
+        }
+        self.keyframes.last().map(|keyframe| keyframe.value)
+    }
+}
+
+// keyframed parameters played back in simulation time
+pub struct Timeline {
+    pub playing: bool,
+    pub looping: bool,
+    pub length: f32,
+    pub time: f32,
+    pub tracks: Vec<Track>,
+    // parameter of the next track added in the timeline window
+    pub new_track_parameter: Parameter,
+}
+
+impl Timeline {
+    pub fn default() -> Self {
+        Self {
+            playing: false,
+            looping: true,
+            length: 10.0,
+            time: 0.0,
+            tracks: vec![],
+            new_track_parameter: Parameter::GravityY,
+        }
+    }
+
+    // moves the playback by the simulated time and returns the values to apply
+    pub fn advance(&mut self, dt: f32) -> Vec<(Parameter, f32)> {
+        if !self.playing {
+            return vec![];
+        }
+        self.time += dt;
+        if self.time >= self.length {
+            if self.looping && self.length > 0.0 {
+                self.time %= self.length;
+            } else {
+                self.time = self.length;
+                self.playing = false;
+            }
+        }
+        self.values()
+    }
+
+    pub fn values(&self) -> Vec<(Parameter, f32)> {
+        self.tracks.iter()
+            .filter(|track| track.enabled)
+            .filter_map(|track| Some((track.parameter, track.value_at(self.time)?)))
+            .collect()
+    }
+}
@@ -1,5 +1,5 @@
 
-use crate::{shaders, context::Context, syntax_highlighting::CodeTheme};
+use crate::{shaders, benchmark::{self, GRID_BENCHMARK_OBJECT_COUNTS, STORAGE_BENCHMARK_OBJECT_COUNT, N_BODY_BENCHMARK_OBJECT_COUNTS}, nbody::NBodyMethod, material::MATERIAL_COUNT, context::Context, solver::{Solver, Broadphase}, container::{ContainerShape, CUSTOM_SDFS}, timeline::{Track, Parameter, Curve}, gravity::{GravityWell, GravityZone, Falloff}, rigidbody::RigidShape, shape::{ShapeKind, MAX_POLYGON_VERTICES}, thermal::{WallHeat, WallHeatMode}, phase::Phase, vector::Vec2, color_map::{ColorMapSource, ColorMapPalette}, syntax_highlighting::CodeTheme, verlet::DEFAULT_OBJECT_COLOR};
 use macroquad::prelude::*;
 use ::rand::{rngs::ThreadRng, thread_rng, Rng};
 
@@ -14,6 +14,8 @@ pub struct Windows {
     pub shaders: bool,
     pub rules: bool,
     pub presets: bool,
+    pub coloring: bool,
+    pub timeline: bool,
 }
 
 impl Windows {
@@ -24,6 +26,8 @@ impl Windows {
             shaders: false,
             rules: false,
             presets: false,
+            coloring: false,
+            timeline: false,
         }
     }
 }
@@ -36,7 +40,9 @@ pub fn render(context: &mut Context, windows: &mut Windows) {
                 ui.checkbox(&mut windows.controls, "Controls");
                 ui.checkbox(&mut windows.simulation, "Simulation");
                 ui.checkbox(&mut windows.rules, "Rules");
+                ui.checkbox(&mut windows.coloring, "Coloring");
                 ui.checkbox(&mut windows.shaders, "Shaders");
+                ui.checkbox(&mut windows.timeline, "Timeline");
                 ui.checkbox(&mut windows.presets, "Presets");
             });
         egui::Window::new("Controls")
@@ -54,12 +60,22 @@ pub fn render(context: &mut Context, windows: &mut Windows) {
             .show(egui_ctx, |ui| {
                 rules(ui, context);
             });
+        egui::Window::new("Coloring")
+            .open(&mut windows.coloring)
+            .show(egui_ctx, |ui| {
+                coloring(ui, context);
+            });
         egui::Window::new("Shaders")
             .open(&mut windows.shaders)
             .default_size((600.0, 500.0))
             .show(egui_ctx, |ui| {
                 shaders(ui, context);
             });
+        egui::Window::new("Timeline")
+            .open(&mut windows.timeline)
+            .show(egui_ctx, |ui| {
+                timeline(ui, context);
+            });
         egui::Window::new("Presets")
             .open(&mut windows.presets)
             .show(egui_ctx, |ui| {
@@ -83,7 +99,26 @@ pub fn controls(ui: &mut egui::Ui, context: &mut Context) {
     ui.separator();
     ui.collapsing("Mass controls", |ui| {
         ui.add(egui::Slider::new(&mut context.spawn_count, 100..=1000).text("Count"));
-        ui.add(egui::Slider::new(&mut context.solver.spawn_radius, 1.0..=50.0).text("Radius"));
+        ui.add(egui::Slider::new(&mut context.solver.spawn_radius, 1.0..=50.0).text("Radius"))
+            .on_hover_text("Bounding radius for shapes other than circles");
+        egui::ComboBox::from_label("Shape")
+            .selected_text(context.solver.spawn_shape.name())
+            .show_ui(ui, |ui| {
+                for shape in ShapeKind::ALL {
+                    ui.selectable_value(&mut context.solver.spawn_shape, shape, shape.name());
+                }
+            });
+        match context.solver.spawn_shape {
+            ShapeKind::Circle => {},
+            ShapeKind::Capsule | ShapeKind::Box => {
+                ui.add(egui::Slider::new(&mut context.solver.spawn_shape_aspect, 0.1..=1.0).text("Aspect (width / length)"));
+            },
+            ShapeKind::Polygon => {
+                ui.add(egui::Slider::new(&mut context.solver.spawn_polygon_sides, 3..=MAX_POLYGON_VERTICES).text("Sides"));
+                ui.checkbox(&mut context.solver.spawn_polygon_random, "Irregular")
+                    .on_hover_text("Every object gets its own random convex polygon");
+            },
+        }
         ui.horizontal(|ui| {
             if ui.button("Spawn").clicked() {
                 context.solver.spawn_count(context.spawn_count);
@@ -95,6 +130,81 @@ pub fn controls(ui: &mut egui::Ui, context: &mut Context) {
                 context.solver.clear();
             }
         });
+        ui.add(egui::Slider::new(&mut context.spawn_rate, 0.0..=1000.0).text("Automatic spawns per second"));
+    });
+
+    ui.separator();
+    ui.collapsing("Soft bodies", |ui| {
+        ui.label(format!("Soft bodies: {}", context.solver.soft_bodies.bodies.len()));
+        ui.add(egui::Slider::new(&mut context.solver.soft_bodies.spawn_radius, 10.0..=300.0).text("Size (ring radius)"))
+            .on_hover_text("Objects of the ring use the spawn radius from above");
+        ui.add(egui::Slider::new(&mut context.solver.soft_bodies.spawn_stiffness, 0.0..=1.0).text("Stiffness"));
+        ui.add(
+            egui::Slider::new(&mut context.solver.soft_bodies.spawn_pressure, 0.0..=20_000.0).text("Pressure")
+        );
+        ui.horizontal(|ui| {
+            if ui.button("Spawn in the middle").clicked() {
+                context.solver.spawn_soft_body(Vec2 {x: screen_width() * 0.5, y: screen_height() * 0.5});
+            }
+            if ui.button("Spawn at random").clicked() {
+                let radius: f32 = context.solver.soft_bodies.spawn_radius + context.solver.spawn_radius;
+                let center: Vec2 = Vec2 {
+                    x: thread_rng().gen_range(radius..(screen_width() - radius).max(radius + 1.0)),
+                    y: thread_rng().gen_range(radius..(screen_height() - radius).max(radius + 1.0)),
+                };
+                context.solver.spawn_soft_body(center);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Apply to all").on_hover_text("Use the stiffness and pressure for existing soft bodies").clicked() {
+                for body in context.solver.soft_bodies.bodies.iter_mut() {
+                    body.stiffness = context.solver.soft_bodies.spawn_stiffness;
+                    body.pressure = context.solver.soft_bodies.spawn_pressure;
+                }
+            }
+            if ui.button("Dissolve all").on_hover_text("Turn the soft bodies into loose objects").clicked() {
+                context.solver.soft_bodies.clear();
+            }
+        });
+    });
+
+    ui.separator();
+    ui.collapsing("Rigid bodies", |ui| {
+        ui.label(format!("Rigid bodies: {}", context.solver.rigid_bodies.bodies.len()));
+        egui::ComboBox::from_label("Shape")
+            .selected_text(context.solver.rigid_bodies.spawn_shape.name())
+            .show_ui(ui, |ui| {
+                for shape in RigidShape::ALL {
+                    ui.selectable_value(&mut context.solver.rigid_bodies.spawn_shape, shape, shape.name());
+                }
+            });
+        ui.add(egui::Slider::new(&mut context.solver.rigid_bodies.spawn_size, 2..=15).text("Size (objects across)"))
+            .on_hover_text("Objects of the shape use the spawn radius from above");
+        ui.add(egui::Slider::new(&mut context.solver.rigid_bodies.spawn_stiffness, 0.0..=1.0).text("Stiffness"))
+            .on_hover_text("Below 1 the shapes give a little and spring back");
+        ui.horizontal(|ui| {
+            if ui.button("Spawn in the middle").clicked() {
+                context.solver.spawn_rigid_body(Vec2 {x: screen_width() * 0.5, y: screen_height() * 0.5});
+            }
+            if ui.button("Spawn at random").clicked() {
+                let radius: f32 = context.solver.rigid_bodies.spawn_size as f32 * context.solver.spawn_radius * 2.0;
+                let center: Vec2 = Vec2 {
+                    x: thread_rng().gen_range(radius..(screen_width() - radius).max(radius + 1.0)),
+                    y: thread_rng().gen_range(radius..(screen_height() - radius).max(radius + 1.0)),
+                };
+                context.solver.spawn_rigid_body(center);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Apply to all").on_hover_text("Use the stiffness for existing rigid bodies").clicked() {
+                for body in context.solver.rigid_bodies.bodies.iter_mut() {
+                    body.stiffness = context.solver.rigid_bodies.spawn_stiffness;
+                }
+            }
+            if ui.button("Dissolve all").on_hover_text("Turn the rigid bodies into loose objects").clicked() {
+                context.solver.rigid_bodies.clear();
+            }
+        });
     });
 
     ui.separator();
@@ -143,11 +253,9 @@ pub fn controls(ui: &mut egui::Ui, context: &mut Context) {
             ui.checkbox(&mut context.shake_auto_random, "After each acceleration");
         });
         if ui.button("Accelerate").clicked() {
-            context.solver.accelerate_all(context.shake_intensity, context.shake_direction.to_radians());
-            if context.shake_auto_random {
-                context.shake_direction = rng.gen_range(0..360) as f32;
-            }
+            context.shake();
         }
+        ui.add(egui::Slider::new(&mut context.shake_rate, 0.0..=10.0).text("Automatic accelerations per second"));
     });
 
     ui.separator();
@@ -173,7 +281,13 @@ pub fn simulation(ui: &mut egui::Ui, context: &mut Context, fps: f32) {
         ui.label(format!("FPS: {:.02} ({:.02}ms)", fps, 1000.0 / fps));
         ui.label(format!("SFPS: {:.02} ({:.02}ms)", 1.0 / context.last_sim_frame_time, context.last_sim_frame_time * 1000.0));
         ui.label(format!("Objects: {}", context.solver.verlet_objects.len()));
-        ui.label(format!("Cell size: {} Grid size: [{}, {}]", context.solver.cell_size, context.solver.cell_grid[0].len(), context.solver.cell_grid.len()));
+        ui.label(format!("Cell size: {} Grid size: [{}, {}]", context.solver.cell_size, context.solver.grid_size().0, context.solver.grid_size().1));
+        ui.label(format!("Pair checks (last substep): {}", context.solver.pair_checks));
+        let (linear, rotational): (f32, f32) = context.solver.kinetic_energy();
+        ui.label(format!(
+            "Kinetic energy: {:.0} linear, {:.0} rotational ({:.01}%)",
+            linear, rotational, 100.0 * rotational / (linear + rotational).max(f32::EPSILON),
+        ));
     });
 
     // delta time values passed into solver.update()
@@ -228,6 +342,12 @@ pub fn simulation(ui: &mut egui::Ui, context: &mut Context, fps: f32) {
         context.sfps_target = 60.0;
     };
 
+    // rendering
+    ui.separator();
+    ui.add_enabled(context.circle_batch.instancing, egui::Checkbox::new(&mut context.batched_rendering, "Batched (instanced) rendering"))
+        .on_hover_text("Draw all objects in one draw call, always used when shaders are enabled")
+        .on_disabled_hover_text("Instanced drawing isn't supported here, objects are drawn one by one");
+
     // substep size
     ui.separator();
     ui.add(
@@ -236,16 +356,103 @@ pub fn simulation(ui: &mut egui::Ui, context: &mut Context, fps: f32) {
     if ui.button("Reset substep count").clicked() {
         context.sim_substeps = 8;
     }
+
+    // collision broadphase
+    ui.separator();
+    ui.collapsing("Broadphase", |ui| {
+        egui::ComboBox::from_label("Broadphase")
+            .selected_text(context.solver.broadphase.name())
+            .show_ui(ui, |ui| {
+                for broadphase in Broadphase::ALL {
+                    ui.selectable_value(&mut context.solver.broadphase, broadphase, broadphase.name());
+                }
+            });
+        if context.solver.broadphase == Broadphase::MultiLevel {
+            ui.label(format!("Grid levels: {}", context.solver.multi_level_grid.levels.len()));
+        }
+        ui.add_enabled(
+            context.solver.broadphase == Broadphase::Flat,
+            egui::Checkbox::new(&mut context.solver.sort_objects_by_cell, "Reorder objects by cell (memory locality)")
+        );
+        ui.add_enabled(
+            context.solver.broadphase == Broadphase::Flat,
+            egui::Checkbox::new(&mut context.solver.solve_pairs_once, "Solve each pair once (half neighbourhood)")
+        ).on_hover_text("Without this every pair is resolved twice per substep, which makes the pile stiffer");
+
+        ui.separator();
+        if Solver::parallel_available() {
+            ui.add_enabled(
+                context.solver.broadphase == Broadphase::Flat && context.solver.solve_pairs_once && !context.solver.wrap_x,
+                egui::Checkbox::new(&mut context.solver.parallel, "Multithreaded collision solving")
+            );
+            ui.add_enabled(
+                context.solver.parallel,
+                egui::Slider::new(&mut context.solver.thread_count, 1..=64).text("Threads")
+            );
+        } else {
+            ui.label("Multithreading requires a native build with the \"parallel\" feature");
+        }
+    });
+
+    // headless benchmarks
+    ui.separator();
+    ui.collapsing("Benchmarks", |ui| {
+        ui.label("Runs the simulation without rendering, the window freezes until it's done");
+        let counts: Vec<String> = GRID_BENCHMARK_OBJECT_COUNTS.iter().map(|count| count.to_string()).collect();
+        if ui.button(format!("Grid benchmark ({} objects)", counts.join("/"))).clicked() {
+            context.benchmark_results = benchmark::grid_benchmark();
+        }
+        if ui.button("Mixed radius benchmark (Density preset)").clicked() {
+            context.benchmark_results = benchmark::density_benchmark();
+        }
+        if ui.button(format!("Storage layout benchmark ({} objects)", STORAGE_BENCHMARK_OBJECT_COUNT)).clicked() {
+            context.benchmark_results = benchmark::storage_benchmark();
+        }
+        let counts: Vec<String> = N_BODY_BENCHMARK_OBJECT_COUNTS.iter().map(|count| count.to_string()).collect();
+        if ui.button(format!("N-body benchmark ({} objects)", counts.join("/"))).clicked() {
+            context.benchmark_results = benchmark::n_body_benchmark();
+        }
+        egui::Grid::new("benchmark_results").striped(true).show(ui, |ui| {
+            ui.label("Configuration");
+            ui.label("Objects");
+            ui.label("Frame time");
+            ui.label("Pair checks");
+            ui.end_row();
+            for result in &context.benchmark_results {
+                ui.label(&result.name);
+                ui.label(result.object_count.to_string());
+                ui.label(format!("{:.02}ms", result.ms_per_frame));
+                ui.label(result.pair_checks.to_string());
+                ui.end_row();
+            }
+        });
+    });
 }
 
 pub fn rules(ui: &mut egui::Ui, context: &mut Context) {
     ui.collapsing("Gravity", |ui| {
         ui.add(
-            egui::Slider::new(&mut context.solver.gravity.x, 0.0..=10_000.0).text("X axis")
+            egui::Slider::new(&mut context.solver.gravity.x, -10_000.0..=10_000.0).text("X axis")
         );
         ui.add(
-            egui::Slider::new(&mut context.solver.gravity.y, 0.0..=10_000.0).text("Y axis")
+            egui::Slider::new(&mut context.solver.gravity.y, -10_000.0..=10_000.0).text("Y axis")
         );
+        let mut magnitude: f32 = context.solver.gravity.len();
+        let mut direction: f32 = context.solver.gravity.y.atan2(context.solver.gravity.x).to_degrees().rem_euclid(360.0);
+        let magnitude_changed: bool = ui.add(
+            egui::Slider::new(&mut magnitude, 0.0..=10_000.0).text("Magnitude")
+        ).changed();
+        let direction_changed: bool = ui.add(
+            egui::Slider::new(&mut direction, 0.0..=360.0)
+                .text("Direction (90° is down)")
+                .custom_formatter(|p, _| {format!("{p:.0}°")})
+        ).changed();
+        if magnitude_changed || direction_changed {
+            context.solver.gravity = Vec2 {
+                x: direction.to_radians().cos() * magnitude,
+                y: direction.to_radians().sin() * magnitude,
+            };
+        }
         ui.horizontal(|ui| {
             if ui.button("Default").clicked() {
                 context.solver.gravity.x = 0.0;
@@ -255,15 +462,325 @@ pub fn rules(ui: &mut egui::Ui, context: &mut Context) {
                 context.solver.gravity.x = 0.0;
                 context.solver.gravity.y = 0.0;
             }
+            if ui.button("Invert").clicked() {
+                context.solver.gravity = context.solver.gravity * -1.0;
+            }
         });
     });
 
+    ui.separator();
+    ui.collapsing("Gravity wells", |ui| {
+        let mut removed: Option<usize> = None;
+        for (i, well) in context.solver.gravity_wells.iter_mut().enumerate() {
+            ui.push_id(("gravity_well", i), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Well {}", i + 1));
+                    ui.add(egui::DragValue::new(&mut well.position.x).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut well.position.y).prefix("y: "));
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                });
+                ui.add(egui::Slider::new(&mut well.strength, -10_000.0..=10_000.0).text("Strength (negative repels)"));
+                egui::ComboBox::from_label("Falloff")
+                    .selected_text(well.falloff.name())
+                    .show_ui(ui, |ui| {
+                        for falloff in Falloff::ALL {
+                            ui.selectable_value(&mut well.falloff, falloff, falloff.name());
+                        }
+                    });
+                ui.add_enabled(
+                    well.falloff == Falloff::InverseSquare,
+                    egui::Slider::new(&mut well.reference_distance, 1.0..=1_000.0).text("Reference distance")
+                ).on_hover_text("Strength is the acceleration at this distance, closer objects get the same acceleration");
+                ui.separator();
+            });
+        }
+        if let Some(i) = removed {
+            context.solver.gravity_wells.remove(i);
+        }
+        if ui.button("Add well at the window center").clicked() {
+            context.solver.gravity_wells.push(GravityWell::new(Vec2 {
+                x: screen_width() * 0.5,
+                y: screen_height() * 0.5,
+            }));
+        }
+    });
+
+    ui.separator();
+    ui.collapsing("Gravity zones", |ui| {
+        ui.label("Objects inside a zone use its gravity instead of the global one");
+        let mut removed: Option<usize> = None;
+        for (i, zone) in context.solver.gravity_zones.iter_mut().enumerate() {
+            ui.push_id(("gravity_zone", i), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Zone {}", i + 1));
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("From");
+                    ui.add(egui::DragValue::new(&mut zone.min.x).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut zone.min.y).prefix("y: "));
+                    ui.label("to");
+                    ui.add(egui::DragValue::new(&mut zone.max.x).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut zone.max.y).prefix("y: "));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Gravity");
+                    ui.add(egui::DragValue::new(&mut zone.gravity.x).speed(10.0).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut zone.gravity.y).speed(10.0).prefix("y: "));
+                });
+                ui.separator();
+            });
+        }
+        if let Some(i) = removed {
+            context.solver.gravity_zones.remove(i);
+        }
+        if ui.button("Add zone (left half of the window, upward)").clicked() {
+            context.solver.gravity_zones.push(GravityZone {
+                min: Vec2::zero(),
+                max: Vec2 {x: screen_width() * 0.5, y: screen_height()},
+                gravity: Vec2 {x: 0.0, y: -1_000.0},
+            });
+        }
+    });
+
+    ui.separator();
+    ui.collapsing("N-body gravity", |ui| {
+        ui.label("Objects attract each other, proportionally to their area");
+        ui.checkbox(&mut context.solver.n_body.enabled, "Enabled");
+        egui::ComboBox::from_label("Method")
+            .selected_text(context.solver.n_body.method.name())
+            .show_ui(ui, |ui| {
+                for method in NBodyMethod::ALL {
+                    ui.selectable_value(&mut context.solver.n_body.method, method, method.name());
+                }
+            });
+        ui.add(
+            egui::Slider::new(&mut context.solver.n_body.gravitational_constant, 0.0..=10_000.0)
+                .logarithmic(true)
+                .text("Gravitational constant")
+        );
+        ui.add(
+            egui::Slider::new(&mut context.solver.n_body.softening, 0.1..=50.0).text("Softening distance")
+        ).on_hover_text("Keeps the pull of close objects finite");
+        ui.add_enabled(
+            context.solver.n_body.method == NBodyMethod::BarnesHut,
+            egui::Slider::new(&mut context.solver.n_body.theta, 0.0..=1.5).text("Opening angle (theta)")
+        ).on_hover_text("Lower is more accurate and slower, zero is the same as the direct sum");
+    });
+
+    ui.separator();
+    ui.collapsing("Electric charge", |ui| {
+        ui.checkbox(&mut context.solver.electrostatics.enabled, "Coulomb forces");
+        ui.add(
+            egui::Slider::new(&mut context.solver.electrostatics.coulomb_constant, 0.0..=100_000_000.0)
+                .logarithmic(true)
+                .text("Coulomb constant")
+        );
+        ui.add(
+            egui::Slider::new(&mut context.solver.electrostatics.cutoff, 1.0..=500.0).text("Cutoff distance")
+        ).on_hover_text("Pairs further apart don't interact, larger distances are slower");
+        ui.add(
+            egui::Slider::new(&mut context.solver.electrostatics.softening, 0.1..=20.0).text("Softening distance")
+        );
+        ui.horizontal(|ui| {
+            ui.label("Electric field");
+            ui.add(egui::DragValue::new(&mut context.solver.electrostatics.electric_field.x).speed(100.0).prefix("x: "));
+            ui.add(egui::DragValue::new(&mut context.solver.electrostatics.electric_field.y).speed(100.0).prefix("y: "));
+            if ui.button("Zero").clicked() {
+                context.solver.electrostatics.electric_field = Vec2::zero();
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut context.solver.spawn_charge, -50.0..=50.0).text("Spawn charge")
+        );
+        ui.checkbox(&mut context.solver.spawn_charge_alternate, "Alternate the sign on spawn (neutral scene)");
+        if ui.button("Recharge all").on_hover_text("Apply the spawn charge rules to every existing object").clicked() {
+            context.solver.recharge_all();
+        }
+    });
+
+    ui.separator();
+    ui.collapsing("Materials", |ui| {
+        egui::ComboBox::from_label("Spawn material")
+            .selected_text(context.solver.materials.name(context.solver.spawn_material))
+            .show_ui(ui, |ui| {
+                for material in 0..MATERIAL_COUNT as u8 {
+                    ui.selectable_value(&mut context.solver.spawn_material, material, context.solver.materials.name(material));
+                }
+            });
+        ui.checkbox(&mut context.solver.materials.cohesion_enabled, "Cohesion (objects pull on each other)");
+        ui.checkbox(&mut context.solver.materials.adhesion_enabled, "Adhesion (objects stick to walls)");
+        ui.add(
+            egui::Slider::new(&mut context.solver.materials.band, 0.01..=1.0).text("Attraction band")
+        ).on_hover_text("How far beyond contact objects still attract, as a fraction of the contact distance");
+        ui.label("Wall adhesion");
+        for material in context.solver.materials.list.iter_mut() {
+            ui.add(egui::Slider::new(&mut material.wall_adhesion, 0.0..=20_000.0).text(material.name));
+        }
+        ui.label("Specific heat (heat capacity per mass, set on spawn)");
+        for material in context.solver.materials.list.iter_mut() {
+            ui.add(egui::Slider::new(&mut material.specific_heat, 0.1..=10.0).text(material.name));
+        }
+        ui.label("Thermal expansion (radius growth per degree, mass stays)");
+        for material in context.solver.materials.list.iter_mut() {
+            ui.add(egui::Slider::new(&mut material.thermal_expansion, 0.0..=0.2).text(material.name));
+        }
+        ui.label("Cohesion between materials");
+        egui::Grid::new("cohesion_matrix").striped(true).show(ui, |ui| {
+            ui.label("");
+            for material in context.solver.materials.list {
+                ui.label(material.name);
+            }
+            ui.end_row();
+            for i in 0..MATERIAL_COUNT {
+                ui.label(context.solver.materials.list[i].name);
+                for j in 0..MATERIAL_COUNT {
+                    // the matrix is symmetric, only the upper half is editable
+                    let mut strength: f32 = context.solver.materials.cohesion[i][j];
+                    let response: egui::Response = ui.add_enabled(
+                        j >= i,
+                        egui::DragValue::new(&mut strength).speed(10.0).clamp_range(0.0..=100_000.0)
+                    );
+                    if response.changed() {
+                        context.solver.materials.set_cohesion(i, j, strength);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    });
+
+    ui.separator();
+    ui.collapsing("Fluid", |ui| {
+        ui.checkbox(&mut context.solver.fluid.enabled, "Solve fluid materials as a liquid");
+        ui.add(egui::Slider::new(&mut context.solver.fluid.iterations, 1..=10).text("Iterations"));
+        ui.add(
+            egui::Slider::new(&mut context.solver.fluid.kernel_factor, 1.0..=4.0).text("Smoothing radius (diameters)")
+        );
+        ui.add(
+            egui::Slider::new(&mut context.solver.fluid.relaxation, 0.0001..=1.0).logarithmic(true).text("Relaxation")
+        ).on_hover_text("Softens the density constraint, higher is more compressible and more stable");
+        ui.add(egui::Slider::new(&mut context.solver.fluid.viscosity, 0.0..=1.0).text("Viscosity"));
+        ui.label("Surface tension is the cohesion between fluid materials, set under Materials");
+        ui.label(format!("Surface flatness: {:.02}px", context.solver.surface_flatness()));
+        if ui.button("Settling check (headless dam break)").on_hover_text("Runs the dam break without rendering, the window freezes until it's done").clicked() {
+            context.fluid_check_result = Some(benchmark::fluid_check(context.solver.world_size));
+        }
+        if let Some(result) = &context.fluid_check_result {
+            ui.label(format!(
+                "{} after {}s: surface flatness {:.02}px, tolerance {:.02}px",
+                if result.passed() {"Passed"} else {"Failed"}, result.seconds, result.flatness, result.tolerance,
+            ));
+        }
+    });
+
+    ui.separator();
+    ui.collapsing("Friction", |ui| {
+        ui.add(egui::Slider::new(&mut context.solver.friction, 0.0..=2.0).text("Friction"))
+            .on_hover_text("Coulomb friction between objects and against the window walls, makes circles roll");
+        ui.checkbox(&mut context.show_rotation_markers, "Rotation markers");
+        ui.label("Container walls use the container wall friction");
+    });
+
     ui.separator();
     ui.collapsing("Constraint enforcement", |ui| {
         ui.checkbox(&mut context.solver.apply_constraint_bottom, "Apply bottom constraint");
         ui.checkbox(&mut context.solver.apply_constraint_top, "Apply top constraint");
         ui.checkbox(&mut context.solver.apply_constraint_left, "Apply left constraint");
         ui.checkbox(&mut context.solver.apply_constraint_right, "Apply right constraint");
+        ui.separator();
+        ui.checkbox(&mut context.solver.wrap_x, "Wrap around horizontally (periodic)");
+        ui.checkbox(&mut context.solver.wrap_y, "Wrap around vertically (periodic)");
+        ui.label("Wrapped axes ignore their constraints");
+    });
+
+    ui.separator();
+    ui.collapsing("Container", |ui| {
+        let container = &mut context.solver.container;
+        egui::ComboBox::from_label("Shape")
+            .selected_text(container.shape.name())
+            .show_ui(ui, |ui| {
+                for shape in ContainerShape::ALL {
+                    ui.selectable_value(&mut container.shape, shape, shape.name());
+                }
+            });
+        ui.label("Sizes are relative to half of the smaller window dimension");
+        match container.shape {
+            ContainerShape::Window => {
+                ui.label("The window uses the per-side constraint, bounce and temperature settings unless it moves, while moving it shrinks to stay on screen");
+            },
+            ContainerShape::Circle => {
+                ui.add(egui::Slider::new(&mut container.radius, 0.1..=2.0).text("Radius"));
+            },
+            ContainerShape::RoundedRectangle => {
+                ui.add(egui::Slider::new(&mut container.half_width, 0.1..=2.0).text("Half width"));
+                ui.add(egui::Slider::new(&mut container.half_height, 0.1..=2.0).text("Half height"));
+                ui.add(egui::Slider::new(&mut container.corner_radius, 0.0..=1.0).text("Corner radius"));
+            },
+            ContainerShape::Annulus => {
+                ui.add(egui::Slider::new(&mut container.radius, 0.1..=2.0).text("Outer radius"));
+                // an inner radius past the outer one leaves no inside at all
+                container.inner_radius = container.inner_radius.min(container.radius - 0.05);
+                ui.add(egui::Slider::new(&mut container.inner_radius, 0.0..=container.radius - 0.05).text("Inner radius"));
+            },
+            ContainerShape::Custom => {
+                ui.horizontal(|ui| {
+                    ui.label("Examples:");
+                    for (name, source) in CUSTOM_SDFS {
+                        if ui.button(name).clicked() {
+                            container.set_custom_sdf(source);
+                        }
+                    }
+                });
+                if ui.text_edit_multiline(&mut container.custom_sdf).changed() {
+                    container.reload_custom_sdf();
+                }
+                ui.label("One part per line or semicolon, parts are added in order, cut removes one")
+                    .on_hover_text("circle x y radius\nbox x y half_width half_height [corner_radius]\ncapsule x1 y1 x2 y2 radius\ne.g. \"cut circle 0 0 0.2\"");
+                if !container.custom_sdf_error.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &container.custom_sdf_error);
+                }
+            },
+        }
+        if !container.is_window() {
+            ui.checkbox(&mut container.bounce, "Container bounce");
+            wall_heat(ui, &mut container.wall_heat, "Container wall heat");
+            ui.add(egui::Slider::new(&mut container.wall_friction, 0.0..=1.0).text("Container wall friction"));
+        }
+
+        ui.separator();
+        ui.checkbox(&mut container.motion.enabled, "Move on a schedule");
+        ui.add_enabled_ui(container.motion.enabled, |ui| {
+            ui.add(egui::Slider::new(&mut container.motion.bpm, 1.0..=600.0).text("BPM"));
+            ui.add(egui::Slider::new(&mut container.motion.shake_amplitude, 0.0..=0.5).text("Shake amplitude"));
+            ui.add(egui::Slider::new(&mut container.motion.turn_angle, 0.0..=360.0).text("Turn angle (degrees)"));
+            ui.horizontal(|ui| {
+                ui.label("Sequence");
+                if ui.text_edit_singleline(&mut container.motion.sequence).changed() {
+                    container.motion.reload_sequence();
+                }
+            });
+            ui.label("One step per beat: hold, cw, ccw or a shake direction in degrees (90 is down)");
+            if !container.motion.sequence_error.is_empty() {
+                ui.colored_label(egui::Color32::RED, &container.motion.sequence_error);
+            }
+        });
+    });
+
+    ui.separator();
+    ui.collapsing("Kill region", |ui| {
+        ui.checkbox(&mut context.solver.kill_oob_objects, "Remove objects outside the kill region");
+        ui.add_enabled(
+            context.solver.kill_oob_objects,
+            egui::Slider::new(&mut context.solver.kill_region_margin, 0.0..=5_000.0).text("Margin around the window")
+        );
+        if context.solver.broadphase != Broadphase::SpatialHash {
+            ui.label("Objects outside of the window only collide and attract or repel each other with the spatial hash broadphase");
+        }
     });
 
     ui.separator();
@@ -286,24 +803,87 @@ pub fn rules(ui: &mut egui::Ui, context: &mut Context) {
         );
         ui.separator();
         ui.add(
-            egui::Slider::new(&mut context.solver.heat_transfer_factor, 0.0..=1.0).text("Heat transfer factor")
-        );
+            egui::Slider::new(&mut context.solver.conductivity, 0.0..=200.0).text("Conductivity")
+        ).on_hover_text("Heat per second per degree of difference through a contact, per unit of the smaller radius");
         ui.add(
-            egui::Slider::new(&mut context.solver.heat_loss_factor, 0.0..=10.0).text("Heat loss factor")
-        );
-        ui.separator();
-        ui.add(
-            egui::Slider::new(&mut context.solver.apply_temperature_bottom, 0.0..=1.0).text("Apply bottom temperature")
-        );
-        ui.add(
-            egui::Slider::new(&mut context.solver.apply_temperature_top, 0.0..=1.0).text("Apply top temperature")
-        );
-        ui.add(
-            egui::Slider::new(&mut context.solver.apply_temperature_left, 0.0..=1.0).text("Apply left temperature")
-        );
+            egui::Slider::new(&mut context.solver.ambient_temperature, -1.0..=1.0).text("Ambient temperature")
+        ).on_hover_text("Objects spawn at it and cool or warm toward it");
         ui.add(
-            egui::Slider::new(&mut context.solver.apply_temperature_right, 0.0..=1.0).text("Apply right temperature")
-        );
+            egui::Slider::new(&mut context.solver.convection, 0.0..=20.0).text("Convection")
+        ).on_hover_text("Heat per second per degree of difference lost to the surroundings, per unit of circumference");
+        ui.separator();
+        wall_heat(ui, &mut context.solver.wall_heat_bottom, "Bottom wall heat");
+        wall_heat(ui, &mut context.solver.wall_heat_top, "Top wall heat");
+        wall_heat(ui, &mut context.solver.wall_heat_left, "Left wall heat");
+        wall_heat(ui, &mut context.solver.wall_heat_right, "Right wall heat");
+    });
+
+    ui.separator();
+    ui.collapsing("Phase changes", |ui| {
+        ui.checkbox(&mut context.solver.phase_changes.enabled, "Melt, freeze, boil and condense");
+        ui.add_enabled_ui(context.solver.phase_changes.enabled, |ui| {
+            let phases: Vec<String> = Phase::ALL.iter()
+                .map(|&phase| {
+                    let count: usize = context.solver.verlet_objects.phase.iter().filter(|&&object_phase| object_phase == phase).count();
+                    format!("{}: {}", phase.name(), count)
+                })
+                .collect();
+            ui.label(phases.join(", "));
+            ui.checkbox(&mut context.solver.phase_changes.latent_heat_enabled, "Latent heat")
+                .on_hover_text("Objects stay at the transition temperature until they took in or gave off the heat of the change");
+            ui.add(egui::Slider::new(&mut context.solver.phase_changes.bond_stiffness, 0.0..=1.0).text("Bond stiffness"))
+                .on_hover_text("Frozen objects bond to the solid objects of their material they touch");
+            ui.add(egui::Slider::new(&mut context.solver.phase_changes.gas_gravity, -2.0..=1.0).text("Gas gravity"))
+                .on_hover_text("Fraction of gravity gas objects feel, below zero they rise");
+            ui.horizontal(|ui| {
+                ui.label(format!("Bonds: {}", context.solver.phase_changes.bonds.len()));
+                if ui.button("Break all").clicked() {
+                    context.solver.phase_changes.clear();
+                }
+            });
+            egui::Grid::new("phase_changes").striped(true).show(ui, |ui| {
+                ui.label("");
+                ui.label("Changes");
+                ui.label("Melting");
+                ui.label("Boiling");
+                ui.label("Latent heat");
+                ui.end_row();
+                for material in context.solver.materials.list.iter_mut() {
+                    ui.label(material.name);
+                    ui.checkbox(&mut material.changes_phase, "");
+                    ui.add(egui::DragValue::new(&mut material.melting_point).speed(0.01).clamp_range(-2.0..=material.boiling_point));
+                    ui.add(egui::DragValue::new(&mut material.boiling_point).speed(0.01).clamp_range(material.melting_point..=5.0));
+                    ui.add(egui::DragValue::new(&mut material.latent_heat).speed(0.1).clamp_range(0.0..=100.0));
+                    ui.end_row();
+                }
+            });
+        });
+    });
+
+    ui.separator();
+    ui.collapsing("Color", |ui| {
+        let mut spawn_color: [f32; 4] = [
+            context.solver.spawn_color.r,
+            context.solver.spawn_color.g,
+            context.solver.spawn_color.b,
+            context.solver.spawn_color.a,
+        ];
+        ui.horizontal(|ui| {
+            if ui.color_edit_button_rgba_unmultiplied(&mut spawn_color).changed() {
+                context.solver.spawn_color = Color::new(spawn_color[0], spawn_color[1], spawn_color[2], spawn_color[3]);
+            }
+            ui.label("Spawn color");
+        });
+        ui.checkbox(&mut context.solver.spawn_color_random_hue, "Randomize hue on spawn (keeps alpha)");
+        ui.horizontal(|ui| {
+            if ui.button("Recolor all").on_hover_text("Apply the spawn color rules to every existing object").clicked() {
+                context.solver.recolor_all();
+            }
+            if ui.button("Default").clicked() {
+                context.solver.spawn_color = DEFAULT_OBJECT_COLOR;
+                context.solver.spawn_color_random_hue = false;
+            }
+        });
     });
 
     ui.separator();
@@ -321,6 +901,74 @@ pub fn rules(ui: &mut egui::Ui, context: &mut Context) {
     });
 }
 
+fn wall_heat(ui: &mut egui::Ui, wall: &mut WallHeat, label: &str) {
+    egui::ComboBox::from_label(label)
+        .selected_text(wall.mode.name())
+        .show_ui(ui, |ui| {
+            for mode in WallHeatMode::ALL {
+                ui.selectable_value(&mut wall.mode, mode, mode.name());
+            }
+        });
+    match wall.mode {
+        WallHeatMode::Insulated => {},
+        WallHeatMode::FixedTemperature => {
+            ui.add(egui::Slider::new(&mut wall.temperature, -1.0..=2.0).text("Wall temperature"))
+                .on_hover_text("Touching objects are conducted toward it like toward another object");
+        },
+        WallHeatMode::HeatFlux => {
+            ui.add(egui::Slider::new(&mut wall.flux, -20_000.0..=20_000.0).text("Heat per second"))
+                .on_hover_text("Added to every touching object, negative cools");
+        },
+    }
+}
+
+pub fn coloring(ui: &mut egui::Ui, context: &mut Context) {
+    ui.label("Color objects on the CPU based on a chosen value (no shaders needed)");
+    egui::ComboBox::from_label("Value")
+        .selected_text(context.color_map.source.name())
+        .show_ui(ui, |ui| {
+            for source in ColorMapSource::ALL {
+                ui.selectable_value(&mut context.color_map.source, source, source.name());
+            }
+        });
+
+    ui.add_enabled_ui(context.color_map.is_enabled(), |ui| {
+        egui::ComboBox::from_label("Palette")
+            .selected_text(context.color_map.palette.name())
+            .show_ui(ui, |ui| {
+                for palette in ColorMapPalette::ALL {
+                    ui.selectable_value(&mut context.color_map.palette, palette, palette.name());
+                }
+            });
+        if context.color_map.palette == ColorMapPalette::Gradient {
+            ui.horizontal(|ui| {
+                let mut start: [f32; 4] = context.color_map.gradient_start.into();
+                if ui.color_edit_button_rgba_unmultiplied(&mut start).changed() {
+                    context.color_map.gradient_start = start.into();
+                }
+                ui.label("Start");
+                let mut end: [f32; 4] = context.color_map.gradient_end.into();
+                if ui.color_edit_button_rgba_unmultiplied(&mut end).changed() {
+                    context.color_map.gradient_end = end.into();
+                }
+                ui.label("End");
+            });
+        }
+
+        ui.separator();
+        ui.checkbox(&mut context.color_map.auto_range, "Automatic range (min/max of current values)");
+        ui.add_enabled(
+            !context.color_map.auto_range,
+            egui::DragValue::new(&mut context.color_map.range_min).speed(0.01).prefix("Min: ")
+        );
+        ui.add_enabled(
+            !context.color_map.auto_range,
+            egui::DragValue::new(&mut context.color_map.range_max).speed(0.01).prefix("Max: ")
+        );
+        ui.checkbox(&mut context.color_map.show_legend, "Show legend");
+    });
+}
+
 pub fn shaders(ui: &mut egui::Ui, context: &mut Context) {
     if cfg!(target_arch = "wasm32") && cfg!(target_os = "unknown") {
         ui.checkbox(&mut context.shader_context.use_shaders, "Use shaders (WARNING!)")
@@ -330,6 +978,9 @@ pub fn shaders(ui: &mut egui::Ui, context: &mut Context) {
     } else {
         ui.checkbox(&mut context.shader_context.use_shaders, "Use shaders");
     }
+    if !context.circle_batch.instancing {
+        ui.label("Shaders need instanced drawing, which isn't supported here");
+    }
 
     ui.separator();
     ui.horizontal(|ui| {
@@ -354,14 +1005,13 @@ pub fn shaders(ui: &mut egui::Ui, context: &mut Context) {
 
     ui.separator();
 
-    ui.collapsing("Enabled uniforms (FPS drops)", |ui| {
-        ui.add_enabled_ui(context.shader_context.use_shaders, |ui| {
-            ui.checkbox(&mut context.shader_context.use_uniform_pos_old, "pos_old - Old object position");
-            ui.checkbox(&mut context.shader_context.use_uniform_pos_curr, "pos_curr - Current object position");
-            ui.checkbox(&mut context.shader_context.use_uniform_acceleration, "acceleration - Object acceleration");
-            ui.checkbox(&mut context.shader_context.use_uniform_radius, "radius - Object radius");
-            ui.checkbox(&mut context.shader_context.use_uniform_temperature, "temperature - Object temperature");
-        });
+    ui.collapsing("Per-object varyings", |ui| {
+        ui.label("Objects are drawn in a single instanced draw call, the default vertex shader passes these on:");
+        ui.label("pos_old - Old object position");
+        ui.label("pos_curr - Current object position");
+        ui.label("radius - Object radius");
+        ui.label("temperature - Object temperature");
+        ui.label("color - Object color (or color map result)");
     });
 
     let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
@@ -420,6 +1070,96 @@ pub fn shaders(ui: &mut egui::Ui, context: &mut Context) {
     );
 }
 
+pub fn timeline(ui: &mut egui::Ui, context: &mut Context) {
+    ui.horizontal(|ui| {
+        let play_text: &str = if context.timeline.playing {"Pause"} else {"Play"};
+        if ui.button(play_text).clicked() {
+            context.timeline.playing = !context.timeline.playing;
+        }
+        if ui.button("Restart").clicked() {
+            context.timeline.time = 0.0;
+        }
+        ui.checkbox(&mut context.timeline.looping, "Loop");
+    });
+    ui.add(egui::Slider::new(&mut context.timeline.length, 0.1..=120.0).text("Length (s)"));
+    let length: f32 = context.timeline.length;
+    if ui.add(egui::Slider::new(&mut context.timeline.time, 0.0..=length).text("Time (s)")).changed() {
+        // scrubbing applies the values right away, even when paused
+        for (parameter, value) in context.timeline.values() {
+            parameter.set(context, value);
+        }
+    }
+    ui.label("Plays in simulated time, keyframes hold their value until the next one");
+
+    ui.separator();
+    let mut removed_track: Option<usize> = None;
+    for track_index in 0..context.timeline.tracks.len() {
+        let parameter: Parameter = context.timeline.tracks[track_index].parameter;
+        let current_value: f32 = parameter.get(context);
+        let time: f32 = context.timeline.time;
+        let track: &mut Track = &mut context.timeline.tracks[track_index];
+        egui::CollapsingHeader::new(parameter.name())
+            .id_source(("timeline_track", track_index))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut track.enabled, "Enabled");
+                    if ui.button("Add keyframe").on_hover_text("At the current time with the current value").clicked() {
+                        track.add(time, current_value, Curve::Linear);
+                    }
+                    if ui.button("Remove track").clicked() {
+                        removed_track = Some(track_index);
+                    }
+                });
+                let mut removed_keyframe: Option<usize> = None;
+                let mut resort: bool = false;
+                egui::Grid::new(("timeline_keyframes", track_index)).striped(true).show(ui, |ui| {
+                    ui.label("Time");
+                    ui.label("Value");
+                    ui.label("Curve to next");
+                    ui.end_row();
+                    for (keyframe_index, keyframe) in track.keyframes.iter_mut().enumerate() {
+                        resort |= ui.add(egui::DragValue::new(&mut keyframe.time).speed(0.05).clamp_range(0.0..=f32::MAX)).changed();
+                        ui.add(egui::DragValue::new(&mut keyframe.value).speed(0.1));
+                        egui::ComboBox::from_id_source(("timeline_curve", track_index, keyframe_index))
+                            .selected_text(keyframe.curve.name())
+                            .show_ui(ui, |ui| {
+                                for curve in Curve::ALL {
+                                    ui.selectable_value(&mut keyframe.curve, curve, curve.name());
+                                }
+                            });
+                        if ui.button("Remove").clicked() {
+                            removed_keyframe = Some(keyframe_index);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(keyframe_index) = removed_keyframe {
+                    track.keyframes.remove(keyframe_index);
+                }
+                if resort {
+                    track.sort();
+                }
+            });
+    }
+    if let Some(track_index) = removed_track {
+        context.timeline.tracks.remove(track_index);
+    }
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("timeline_new_track")
+            .selected_text(context.timeline.new_track_parameter.name())
+            .show_ui(ui, |ui| {
+                for parameter in Parameter::ALL {
+                    ui.selectable_value(&mut context.timeline.new_track_parameter, parameter, parameter.name());
+                }
+            });
+        if ui.button("Add track").clicked() {
+            context.timeline.tracks.push(Track::new(context.timeline.new_track_parameter));
+        }
+    });
+}
+
 pub fn presets(ui: &mut egui::Ui, context: &mut Context) {
     ui.label("Current preset:");
     ui.code(&context.current_preset_name);
@@ -457,6 +1197,48 @@ pub fn presets(ui: &mut egui::Ui, context: &mut Context) {
         if ui.button("Bowling/Pool").on_hover_text("No gravity and boundaries, spawn an object next to another to make them bounce").clicked() {
             context.bowling_pool_preset();
         }
+        if ui.button("Circle container").on_hover_text("Fill a circular container like in the original verlet video").clicked() {
+            context.circle_container_preset();
+        }
+        if ui.button("Mixer").on_hover_text("Fill a square container turning clockwise and counterclockwise").clicked() {
+            context.mixer_preset();
+        }
+        if ui.button("Auto shake").on_hover_text("Timeline shaking the objects twice a second while turning the direction around").clicked() {
+            context.auto_shake_preset();
+        }
+        if ui.button("Orbit").on_hover_text("No gravity and walls, objects circling an attracting point").clicked() {
+            context.orbit_preset();
+        }
+        if ui.button("Zero-g attractor").on_hover_text("No gravity, objects clumping around a constant pull toward the center").clicked() {
+            context.attractor_preset();
+        }
+        if ui.button("Accretion").on_hover_text("Swirling cloud of small objects clumping together under their own gravity").clicked() {
+            context.accretion_preset();
+        }
+        if ui.button("Ionic crystal").on_hover_text("Alternating charges settling into a lattice").clicked() {
+            context.ionic_crystal_preset();
+        }
+        if ui.button("Plasma").on_hover_text("Charged gas with wrap-around walls, try adding an electric field").clicked() {
+            context.plasma_preset();
+        }
+        if ui.button("Dam break").on_hover_text("Column of water collapsing and settling").clicked() {
+            context.dam_break_preset();
+        }
+        if ui.button("Jelly").on_hover_text("Soft bodies falling into a pile of loose objects").clicked() {
+            context.jelly_preset();
+        }
+        if ui.button("Tumbling shapes").on_hover_text("Rigid shapes of every kind dropped onto a pile of loose objects").clicked() {
+            context.tumbling_shapes_preset();
+        }
+        if ui.button("Shape pile").on_hover_text("Circles, capsules, boxes and irregular polygons settling into a pile").clicked() {
+            context.shape_pile_preset();
+        }
+        if ui.button("Melting ice").on_hover_text("Ice cube melting in a hot box, the water boiling off the walls and raining back down").clicked() {
+            context.melting_ice_preset();
+        }
+        if ui.button("Sticky goo").on_hover_text("Goo thrown at the ceiling, clumping and sticking to it").clicked() {
+            context.sticky_goo_preset();
+        }
         if ui.button("Density showcase").on_hover_text("Spawn objects of multiple diameters to showcase density").clicked() {
             context.density_preset();
         }
@@ -1,7 +1,7 @@
 
-use std::ops::{Sub, Add, Mul, AddAssign, Div, SubAssign};
+use std::ops::{Sub, Add, Mul, AddAssign, Div, SubAssign, Neg};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
@@ -23,10 +23,31 @@ impl Vec2 {
         self.x.is_nan() || self.y.is_nan()
     }
 
-    pub fn as_tuple(&self) -> (f32, f32) {
-        (self.x, self.y)
+    pub fn dot(&self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y
     }
-}
+
+    // z component of the 3D cross product
+    pub fn cross(&self, other: Self) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    // rotated a quarter turn, clockwise on screen
+    pub fn perpendicular(&self) -> Self {
+        Self {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
+    pub fn rotated(&self, angle: f32) -> Self {
+        let (sin, cos): (f32, f32) = angle.sin_cos();
+        Self {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+        }
+    }
+}
 
 impl Sub for Vec2 {
     type Output = Self;
@@ -46,6 +67,17 @@ impl SubAssign for Vec2 {
     }
 }
 
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
 impl Add for Vec2 {
     type Output = Self;
 
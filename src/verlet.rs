@@ -1,23 +1,166 @@
+use macroquad::prelude::Color;
+use crate::{vector::Vec2, shape::Shape, phase::Phase};
 
-use crate::vector::Vec2;
+pub const DEFAULT_OBJECT_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.5);
 
-#[derive(Clone, Copy)]
-pub struct VerletObject {
-    pub position_current: Vec2,
-    pub position_old: Vec2,
-    pub acceleration: Vec2,
-    pub radius: f32,
-    pub temperature: f32,
+// declares both the single object type and the structure-of-arrays storage from one
+// field list so that adding a per-object property only takes one line
+macro_rules! verlet_object_fields {
+    ($($field:ident: $field_type:ty,)*) => {
+        #[derive(Clone, Copy)]
+        pub struct VerletObject {
+            $(pub $field: $field_type,)*
+        }
+
+        // structure-of-arrays storage, hot loops work on the field vectors directly,
+        // single objects are read with get and written through the fields at their index
+        #[derive(Clone)]
+        pub struct VerletObjects {
+            $(pub $field: Vec<$field_type>,)*
+        }
+
+        impl VerletObjects {
+            pub fn new() -> Self {
+                Self {
+                    $($field: vec![],)*
+                }
+            }
+
+            pub fn push(&mut self, obj: VerletObject) {
+                $(self.$field.push(obj.$field);)*
+            }
+
+            pub fn get(&self, i: usize) -> VerletObject {
+                VerletObject {
+                    $($field: self.$field[i],)*
+                }
+            }
+
+            pub fn remove(&mut self, i: usize) {
+                $(self.$field.remove(i);)*
+            }
+
+            // removes the oldest objects
+            pub fn remove_front(&mut self, count: usize) {
+                let count: usize = count.min(self.len());
+                $(self.$field.drain(0..count);)*
+            }
+
+            pub fn clear(&mut self) {
+                $(self.$field.clear();)*
+            }
+
+            // object i of the result is object order[i] of the original
+            pub fn permute(&mut self, order: &[usize]) {
+                $(self.$field = order.iter().map(|&i| self.$field[i]).collect();)*
+            }
+
+            #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+            pub fn shared(&mut self) -> SharedVerletObjects {
+                SharedVerletObjects {
+                    $($field: self.$field.as_mut_ptr(),)*
+                }
+            }
+        }
+
+        // raw view of the storage for solving collisions on multiple threads at once, every
+        // access is unsafe as the caller has to make sure no two threads touch the same object
+        // and that the storage isn't resized while the view is in use,
+        // collision solving only needs some of the fields
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        #[derive(Clone, Copy)]
+        #[allow(dead_code)]
+        pub struct SharedVerletObjects {
+            $(pub $field: *mut $field_type,)*
+        }
+    };
+}
+
+verlet_object_fields! {
+    position_current: Vec2,
+    position_old: Vec2,
+    acceleration: Vec2,
+    radius: f32,
+    // radius at temperature zero, thermal expansion scales the radius from it while the mass stays
+    rest_radius: f32,
+    temperature: f32,
+    // heat needed to warm the object by one degree
+    heat_capacity: f32,
+    phase: Phase,
+    // latent heat taken in (positive) or given off (negative) by an unfinished phase change
+    phase_heat: f32,
+    // looked for solid neighbours to bond to since the last phase change
+    bond_checked: bool,
+    charge: f32,
+    // index into the solver's materials
+    material: u8,
+    color: Color,
+    // collision shape turned by angle, angle_old is the angle of the previous substep
+    shape: Shape,
+    angle: f32,
+    angle_old: f32,
+
+    // collision diagnostics of the last substep, used for color mapping
+    pressure: f32,
+    contact_count: u32,
+}
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+unsafe impl Send for SharedVerletObjects {}
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+unsafe impl Sync for SharedVerletObjects {}
+
+// objects are flat discs, so their mass goes with their area at the rest radius,
+// other shapes weigh the same as their bounding disc
+pub fn mass(radius: f32) -> f32 {
+    radius * radius
+}
+
+// keeps a list of object indices (soft body members etc.) valid after the object at index
+// was removed, returns where the removed object was in the list, the caller drops that entry
+pub fn index_removed(indices: &mut [usize], index: usize) -> Option<usize> {
+    let mut removed: Option<usize> = None;
+    for (slot, i) in indices.iter_mut().enumerate() {
+        if *i == index {
+            removed = Some(slot);
+        } else if *i > index {
+            *i -= 1;
+        }
+    }
+    removed
+}
+
+// new index of every object after VerletObjects::permute with the given order
+pub fn inverse_order(order: &[usize]) -> Vec<usize> {
+    let mut new_indices: Vec<usize> = vec![0; order.len()];
+    for (new_index, &old_index) in order.iter().enumerate() {
+        new_indices[old_index] = new_index;
+    }
+    new_indices
 }
 
 impl VerletObject {
-    pub fn new(pos: Vec2, radius: f32) -> Self {
+    pub fn new(pos: Vec2, radius: f32, color: Color) -> Self {
         Self {
             position_current: pos.clone(),
             position_old: pos.clone(),
             acceleration: Vec2::zero(),
             radius,
+            rest_radius: radius,
             temperature: 0.0,
+            heat_capacity: mass(radius),
+            phase: Phase::Liquid,
+            phase_heat: 0.0,
+            bond_checked: false,
+            charge: 0.0,
+            material: 0,
+            color,
+            shape: Shape::Circle,
+            angle: 0.0,
+            angle_old: 0.0,
+
+            pressure: 0.0,
+            contact_count: 0,
         }
     }
 
@@ -32,3 +175,17 @@ impl VerletObject {
         self.acceleration += acc;
     }
 }
+
+impl VerletObjects {
+    pub fn len(&self) -> usize {
+        self.position_current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position_current.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = VerletObject> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+}